@@ -104,22 +104,16 @@ macro_rules! feature_enabled {
 /// Tauri command to get enabled features at runtime
 #[tauri::command]
 pub fn get_enabled_features() -> Vec<&'static str> {
-    let mut features = Vec::new();
-    
-    #[cfg(feature = "notifications")]
-    features.push("notifications");
-    
-    #[cfg(feature = "deep-links")]
-    features.push("deep-links");
-    
-    #[cfg(feature = "clipboard")]
-    features.push("clipboard");
-    
-    #[cfg(feature = "system-tray")]
-    features.push("system-tray");
-    
-    #[cfg(feature = "window-manager")]
-    features.push("window-manager");
-    
-    features
+    vec![
+        #[cfg(feature = "notifications")]
+        "notifications",
+        #[cfg(feature = "deep-links")]
+        "deep-links",
+        #[cfg(feature = "clipboard")]
+        "clipboard",
+        #[cfg(feature = "system-tray")]
+        "system-tray",
+        #[cfg(feature = "window-manager")]
+        "window-manager",
+    ]
 }
\ No newline at end of file
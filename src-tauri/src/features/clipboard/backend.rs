@@ -0,0 +1,420 @@
+//! Clipboard backends
+//!
+//! All clipboard I/O of this feature goes through the `ClipboardBackend` trait.
+//! The plugin-backed implementation talks to the system clipboard, while the
+//! in-memory implementation lets the history, detection and dedup logic run
+//! on machines without a display server.
+
+use crate::features::clipboard::utils::is_file_list;
+use regex::Regex;
+use std::sync::{Arc, Mutex, OnceLock};
+use tauri::image::Image;
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+/// Raw RGBA image as exchanged with the clipboard
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClipboardImage {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+/// Read and write access to a clipboard
+pub trait ClipboardBackend: Send + Sync {
+    fn read_text(&self) -> Result<String, String>;
+    fn write_text(&self, text: &str) -> Result<(), String>;
+    fn read_image(&self) -> Result<ClipboardImage, String>;
+    fn write_image(&self, image: &ClipboardImage) -> Result<(), String>;
+    fn read_html(&self) -> Result<String, String>;
+    fn write_html(&self, html: &str, alt_text: Option<&str>) -> Result<(), String>;
+    fn read_files(&self) -> Result<Vec<String>, String>;
+    fn write_files(&self, paths: &[String]) -> Result<(), String>;
+    fn clear(&self) -> Result<(), String>;
+
+    /// Write text flagged so clipboard managers and OS clipboard history skip it
+    /// Backends without such hints write plain text
+    fn write_secret_text(&self, text: &str) -> Result<(), String> {
+        self.write_text(text)
+    }
+}
+
+/// Clipboard backend owned by the clipboard worker thread
+pub type ClipboardBackendState = Arc<dyn ClipboardBackend>;
+
+/// Create the clipboard backend used by the clipboard commands
+/// Set `CLIPBOARD_BACKEND=memory` to run without access to the system clipboard
+pub fn init_clipboard_backend(app: &tauri::AppHandle) -> ClipboardBackendState {
+    match std::env::var("CLIPBOARD_BACKEND").as_deref() {
+        Ok("memory") => Arc::new(MemoryClipboardBackend::new()),
+        _ => Arc::new(PluginClipboardBackend::new(app.clone())),
+    }
+}
+
+/// Backend using the system clipboard through tauri-plugin-clipboard-manager
+pub struct PluginClipboardBackend {
+    app: tauri::AppHandle,
+}
+
+impl PluginClipboardBackend {
+    pub fn new(app: tauri::AppHandle) -> Self {
+        Self { app }
+    }
+}
+
+impl ClipboardBackend for PluginClipboardBackend {
+    fn read_text(&self) -> Result<String, String> {
+        self.app.clipboard()
+            .read_text()
+            .map_err(|e| format!("Failed to read from clipboard: {}", e))
+    }
+
+    fn write_text(&self, text: &str) -> Result<(), String> {
+        self.app.clipboard()
+            .write_text(text)
+            .map_err(|e| format!("Failed to copy to clipboard: {}", e))
+    }
+
+    fn read_image(&self) -> Result<ClipboardImage, String> {
+        let image = self.app.clipboard()
+            .read_image()
+            .map_err(|e| format!("No image available on the clipboard: {}", e))?;
+        Ok(ClipboardImage {
+            width: image.width(),
+            height: image.height(),
+            rgba: image.rgba().to_vec(),
+        })
+    }
+
+    fn write_image(&self, image: &ClipboardImage) -> Result<(), String> {
+        let image = Image::new(&image.rgba, image.width, image.height);
+        self.app.clipboard()
+            .write_image(&image)
+            .map_err(|e| format!("Failed to copy image to clipboard: {}", e))
+    }
+
+    fn read_html(&self) -> Result<String, String> {
+        // The plugin cannot read the HTML target, so fall back to text that looks like HTML
+        let text = self.read_text()?;
+        if looks_like_html(&text) {
+            Ok(text)
+        } else {
+            Err("No HTML content on the clipboard".to_string())
+        }
+    }
+
+    fn write_html(&self, html: &str, alt_text: Option<&str>) -> Result<(), String> {
+        // Without alt text the markup itself is offered as plain text
+        self.app.clipboard()
+            .write_html(html, Some(alt_text.unwrap_or(html)))
+            .map_err(|e| format!("Failed to copy HTML to clipboard: {}", e))
+    }
+
+    fn read_files(&self) -> Result<Vec<String>, String> {
+        // File lists are exchanged as newline separated paths
+        let text = self.read_text()?;
+        if !is_file_list(&text) {
+            return Err("No file list on the clipboard".to_string());
+        }
+        Ok(text.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| line.to_string())
+            .collect())
+    }
+
+    fn write_files(&self, paths: &[String]) -> Result<(), String> {
+        self.app.clipboard()
+            .write_text(paths.join("\n"))
+            .map_err(|e| format!("Failed to copy files to clipboard: {}", e))
+    }
+
+    fn clear(&self) -> Result<(), String> {
+        self.app.clipboard()
+            .clear()
+            .map_err(|e| format!("Failed to clear clipboard: {}", e))
+    }
+
+    fn write_secret_text(&self, text: &str) -> Result<(), String> {
+        let mut clipboard = arboard::Clipboard::new()
+            .map_err(|e| format!("Failed to access clipboard: {}", e))?;
+        let set = clipboard.set();
+
+        #[cfg(target_os = "linux")]
+        let set = {
+            // Adds the x-kde-passwordManagerHint target
+            use arboard::SetExtLinux;
+            set.exclude_from_history()
+        };
+
+        #[cfg(target_os = "windows")]
+        let set = {
+            // Adds ExcludeClipboardContentFromMonitorProcessing, CanIncludeInClipboardHistory and CanUploadToCloudClipboard
+            use arboard::SetExtWindows;
+            set.exclude_from_monitoring().exclude_from_history().exclude_from_cloud()
+        };
+
+        #[cfg(target_os = "macos")]
+        let set = {
+            // Adds org.nspasteboard.ConcealedType
+            use arboard::SetExtApple;
+            set.exclude_from_history()
+        };
+
+        set.text(text)
+            .map_err(|e| format!("Failed to copy secret to clipboard: {}", e))
+    }
+}
+
+/// Elements written without an end tag
+const VOID_ELEMENTS: [&str; 6] = ["br", "hr", "img", "input", "link", "meta"];
+
+/// Whether text contains markup: a doctype, a void or self-closing element, or an element with its end tag
+/// A lone `<` as in `a < b` is not markup
+fn looks_like_html(text: &str) -> bool {
+    static START_TAG: OnceLock<Regex> = OnceLock::new();
+    let start_tag = START_TAG.get_or_init(|| {
+        Regex::new(r"<([a-z][a-z0-9-]*)(?:\s[^<>]*)?/?>").expect("start tag pattern is valid")
+    });
+
+    let text = text.to_ascii_lowercase();
+    if text.contains("<!doctype html") {
+        return true;
+    }
+    start_tag.captures_iter(&text).any(|tag| {
+        let name = &tag[1];
+        tag[0].ends_with("/>") || VOID_ELEMENTS.contains(&name) || text.contains(&format!("</{}>", name))
+    })
+}
+
+/// Contents of the in-memory clipboard; every write replaces all formats like a real clipboard
+#[derive(Debug, Default, Clone)]
+struct MemoryClipboardContents {
+    text: Option<String>,
+    html: Option<String>,
+    image: Option<ClipboardImage>,
+    files: Option<Vec<String>>,
+}
+
+/// Backend keeping the clipboard in memory, for headless environments and tests
+#[derive(Debug, Default)]
+pub struct MemoryClipboardBackend {
+    contents: Mutex<MemoryClipboardContents>,
+}
+
+impl MemoryClipboardBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn replace(&self, contents: MemoryClipboardContents) -> Result<(), String> {
+        let mut current = self.contents.lock().map_err(|e| e.to_string())?;
+        *current = contents;
+        Ok(())
+    }
+
+    fn read<T>(&self, what: &str, read: impl FnOnce(&MemoryClipboardContents) -> Option<T>) -> Result<T, String> {
+        let contents = self.contents.lock().map_err(|e| e.to_string())?;
+        read(&contents).ok_or_else(|| format!("Clipboard does not contain {}", what))
+    }
+}
+
+impl ClipboardBackend for MemoryClipboardBackend {
+    fn read_text(&self) -> Result<String, String> {
+        self.read("text", |contents| contents.text.clone())
+    }
+
+    fn write_text(&self, text: &str) -> Result<(), String> {
+        self.replace(MemoryClipboardContents {
+            text: Some(text.to_string()),
+            ..Default::default()
+        })
+    }
+
+    fn read_image(&self) -> Result<ClipboardImage, String> {
+        self.read("an image", |contents| contents.image.clone())
+    }
+
+    fn write_image(&self, image: &ClipboardImage) -> Result<(), String> {
+        if image.rgba.len() != image.width as usize * image.height as usize * 4 {
+            return Err("Image data does not match its dimensions".to_string());
+        }
+        self.replace(MemoryClipboardContents {
+            image: Some(image.clone()),
+            ..Default::default()
+        })
+    }
+
+    fn read_html(&self) -> Result<String, String> {
+        self.read("HTML", |contents| contents.html.clone())
+    }
+
+    fn write_html(&self, html: &str, alt_text: Option<&str>) -> Result<(), String> {
+        self.replace(MemoryClipboardContents {
+            text: Some(alt_text.unwrap_or(html).to_string()),
+            html: Some(html.to_string()),
+            ..Default::default()
+        })
+    }
+
+    fn read_files(&self) -> Result<Vec<String>, String> {
+        self.read("a file list", |contents| contents.files.clone())
+    }
+
+    fn write_files(&self, paths: &[String]) -> Result<(), String> {
+        self.replace(MemoryClipboardContents {
+            text: Some(paths.join("\n")),
+            files: Some(paths.to_vec()),
+            ..Default::default()
+        })
+    }
+
+    fn clear(&self) -> Result<(), String> {
+        self.replace(MemoryClipboardContents::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::clipboard::utils::{capture_clipboard, compute_history_stats, detect_clipboard_formats, get_clipboard_history_storage, record_text_in_history};
+
+    // Capture works on process-wide history, so tests that capture run one at a time
+    static CAPTURE_LOCK: Mutex<()> = Mutex::new(());
+
+    fn solid_image(width: u32, height: u32, pixel: [u8; 4]) -> ClipboardImage {
+        ClipboardImage {
+            width,
+            height,
+            rgba: pixel.repeat((width * height) as usize),
+        }
+    }
+
+    fn history_contents() -> Vec<String> {
+        get_clipboard_history_storage().lock().unwrap().iter().map(|item| item.primary_content.clone()).collect()
+    }
+
+    #[test]
+    fn memory_backend_writes_replace_every_format() {
+        let backend = MemoryClipboardBackend::new();
+        backend.write_html("<b>bold</b>", Some("bold")).unwrap();
+        assert_eq!(backend.read_text().unwrap(), "bold");
+        assert_eq!(backend.read_html().unwrap(), "<b>bold</b>");
+
+        backend.write_files(&["/tmp/a.txt".to_string()]).unwrap();
+        assert!(backend.read_html().is_err());
+        assert_eq!(backend.read_files().unwrap(), vec!["/tmp/a.txt".to_string()]);
+
+        assert!(backend.write_image(&ClipboardImage { width: 2, height: 2, rgba: vec![0; 3] }).is_err());
+        backend.clear().unwrap();
+        assert!(backend.read_text().is_err());
+    }
+
+    #[test]
+    fn html_needs_real_markup() {
+        assert!(looks_like_html("<b>bold</b>"));
+        assert!(looks_like_html("line<br>break"));
+        assert!(looks_like_html("<!DOCTYPE html><p>"));
+        assert!(!looks_like_html("a < b"));
+        assert!(!looks_like_html("if a<b && c>d"));
+        assert!(!looks_like_html("Vec<String>"));
+    }
+
+    #[test]
+    fn capture_records_each_copy_once() {
+        let _guard = CAPTURE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        get_clipboard_history_storage().lock().unwrap().clear();
+        let backend = MemoryClipboardBackend::new();
+        let before = compute_history_stats(0).unwrap();
+
+        backend.write_text("backend test: first copy").unwrap();
+        assert_eq!(capture_clipboard(&backend).history.len(), 1);
+        // Polling the unchanged clipboard records nothing
+        assert!(capture_clipboard(&backend).history.is_empty());
+
+        backend.write_text("backend test: second copy").unwrap();
+        capture_clipboard(&backend);
+        backend.write_text("backend test: first copy").unwrap();
+        assert!(capture_clipboard(&backend).history.is_empty());
+
+        let after = compute_history_stats(0).unwrap();
+        assert_eq!(history_contents(), vec!["backend test: second copy", "backend test: first copy"]);
+        assert_eq!(after.total_copies - before.total_copies, 3);
+        assert_eq!(after.duplicate_hits - before.duplicate_hits, 1);
+    }
+
+    #[test]
+    fn app_copies_added_to_history_count_once() {
+        let _guard = CAPTURE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        get_clipboard_history_storage().lock().unwrap().clear();
+        let backend = MemoryClipboardBackend::new();
+        let before = compute_history_stats(0).unwrap();
+
+        // As the frontend does: copy, then add to history, then the monitor polls
+        backend.write_text("backend test: app copy").unwrap();
+        assert!(record_text_in_history(&backend, "backend test: app copy").is_some());
+        assert!(capture_clipboard(&backend).history.is_empty());
+
+        // The monitor may also poll between the copy and the history call
+        backend.write_text("backend test: polled app copy").unwrap();
+        assert_eq!(capture_clipboard(&backend).history.len(), 1);
+        assert!(record_text_in_history(&backend, "backend test: polled app copy").is_none());
+
+        let after = compute_history_stats(0).unwrap();
+        assert_eq!(after.total_copies - before.total_copies, 2);
+        assert_eq!(after.duplicate_hits - before.duplicate_hits, 0);
+    }
+
+    #[test]
+    fn stats_skip_expired_items() {
+        let _guard = CAPTURE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        get_clipboard_history_storage().lock().unwrap().clear();
+        let backend = MemoryClipboardBackend::new();
+
+        backend.write_text("backend test: kept").unwrap();
+        capture_clipboard(&backend);
+        backend.write_text("backend test: expired").unwrap();
+        capture_clipboard(&backend);
+        get_clipboard_history_storage().lock().unwrap()[0].expires_at = Some(0);
+
+        let stats = compute_history_stats(0).unwrap();
+        assert_eq!(stats.total_items, 1);
+        assert_eq!(stats.total_size, "backend test: kept".len());
+    }
+
+    #[test]
+    fn capture_detects_content_and_formats() {
+        let _guard = CAPTURE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        get_clipboard_history_storage().lock().unwrap().clear();
+        let backend = MemoryClipboardBackend::new();
+
+        backend.write_text("https://example.com/backend-test").unwrap();
+        capture_clipboard(&backend);
+        let history = get_clipboard_history_storage().lock().unwrap().clone();
+        assert_eq!(history[0].content_type, "URL");
+        assert!(history[0].formats.iter().any(|format| format.format_name == "Plain Text"));
+
+        backend.write_image(&solid_image(2, 2, [255, 0, 0, 255])).unwrap();
+        let formats = detect_clipboard_formats(&backend);
+        assert!(formats.iter().any(|format| format.format_type == "image/png"));
+        assert!(formats.iter().all(|format| format.raw_data.is_none()));
+    }
+
+    #[test]
+    fn capture_dedupes_images_by_pixels() {
+        let _guard = CAPTURE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        get_clipboard_history_storage().lock().unwrap().clear();
+        let backend = MemoryClipboardBackend::new();
+
+        backend.write_image(&solid_image(3, 2, [255, 0, 0, 255])).unwrap();
+        assert_eq!(capture_clipboard(&backend).history.len(), 1);
+        // Same size, different pixels
+        backend.write_image(&solid_image(3, 2, [0, 0, 255, 255])).unwrap();
+        assert_eq!(capture_clipboard(&backend).history.len(), 1);
+        // Copying the first image again
+        backend.write_image(&solid_image(3, 2, [255, 0, 0, 255])).unwrap();
+        assert!(capture_clipboard(&backend).history.is_empty());
+
+        let history = get_clipboard_history_storage().lock().unwrap().clone();
+        assert_eq!(history.len(), 2);
+        assert!(history.iter().all(|item| item.content_type == "Image" && item.image_data.is_some()));
+    }
+}
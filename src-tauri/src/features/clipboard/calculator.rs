@@ -0,0 +1,405 @@
+//! Inline calculator and unit conversion
+//!
+//! Copied text such as `(1200*1.19)/12`, `72 in to cm` or `3 GiB in MB` is
+//! evaluated with a small recursive descent parser. Only numbers, `+ - * / ^`
+//! and parentheses are understood; nothing is ever executed.
+
+use crate::features::clipboard::types::CalculationResult;
+
+/// Longer input is not treated as a calculation
+const MAX_EXPRESSION_LENGTH: usize = 256;
+
+/// Parentheses nesting limit, keeps recursion bounded
+const MAX_NESTING_DEPTH: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Dimension {
+    Length,
+    Mass,
+    Data,
+    Time,
+    Volume,
+    Temperature,
+}
+
+struct Unit {
+    names: &'static [&'static str],
+    dimension: Dimension,
+    factor: f64, // Size of one unit in the dimension's base unit
+    offset: f64, // Added after scaling, only used by temperatures
+}
+
+const fn unit(names: &'static [&'static str], dimension: Dimension, factor: f64) -> Unit {
+    Unit { names, dimension, factor, offset: 0.0 }
+}
+
+const UNITS: &[Unit] = &[
+    // Length, base unit metre
+    unit(&["mm", "millimeter", "millimeters", "millimetre", "millimetres"], Dimension::Length, 0.001),
+    unit(&["cm", "centimeter", "centimeters", "centimetre", "centimetres"], Dimension::Length, 0.01),
+    unit(&["m", "meter", "meters", "metre", "metres"], Dimension::Length, 1.0),
+    unit(&["km", "kilometer", "kilometers", "kilometre", "kilometres"], Dimension::Length, 1000.0),
+    unit(&["in", "inch", "inches", "\""], Dimension::Length, 0.0254),
+    unit(&["ft", "foot", "feet", "'"], Dimension::Length, 0.3048),
+    unit(&["yd", "yard", "yards"], Dimension::Length, 0.9144),
+    unit(&["mi", "mile", "miles"], Dimension::Length, 1609.344),
+    // Mass, base unit gram
+    unit(&["mg", "milligram", "milligrams"], Dimension::Mass, 0.001),
+    unit(&["g", "gram", "grams"], Dimension::Mass, 1.0),
+    unit(&["kg", "kilogram", "kilograms"], Dimension::Mass, 1000.0),
+    unit(&["t", "tonne", "tonnes"], Dimension::Mass, 1_000_000.0),
+    unit(&["oz", "ounce", "ounces"], Dimension::Mass, 28.349523125),
+    unit(&["lb", "lbs", "pound", "pounds"], Dimension::Mass, 453.59237),
+    // Data, base unit byte; decimal prefixes are powers of 1000, binary ones of 1024
+    unit(&["bit", "bits"], Dimension::Data, 0.125),
+    unit(&["B", "byte", "bytes"], Dimension::Data, 1.0),
+    unit(&["KB", "kB", "kilobyte", "kilobytes"], Dimension::Data, 1e3),
+    unit(&["MB", "megabyte", "megabytes"], Dimension::Data, 1e6),
+    unit(&["GB", "gigabyte", "gigabytes"], Dimension::Data, 1e9),
+    unit(&["TB", "terabyte", "terabytes"], Dimension::Data, 1e12),
+    unit(&["KiB", "kibibyte", "kibibytes"], Dimension::Data, 1024.0),
+    unit(&["MiB", "mebibyte", "mebibytes"], Dimension::Data, 1_048_576.0),
+    unit(&["GiB", "gibibyte", "gibibytes"], Dimension::Data, 1_073_741_824.0),
+    unit(&["TiB", "tebibyte", "tebibytes"], Dimension::Data, 1_099_511_627_776.0),
+    // Time, base unit second
+    unit(&["ms", "millisecond", "milliseconds"], Dimension::Time, 0.001),
+    unit(&["s", "sec", "second", "seconds"], Dimension::Time, 1.0),
+    unit(&["min", "minute", "minutes"], Dimension::Time, 60.0),
+    unit(&["h", "hr", "hour", "hours"], Dimension::Time, 3600.0),
+    unit(&["d", "day", "days"], Dimension::Time, 86400.0),
+    unit(&["wk", "week", "weeks"], Dimension::Time, 604800.0),
+    // Volume, base unit litre
+    unit(&["ml", "milliliter", "milliliters", "millilitre", "millilitres"], Dimension::Volume, 0.001),
+    unit(&["l", "liter", "liters", "litre", "litres"], Dimension::Volume, 1.0),
+    unit(&["gal", "gallon", "gallons"], Dimension::Volume, 3.785411784),
+    unit(&["floz"], Dimension::Volume, 0.0295735295625),
+    // Temperature, base unit kelvin
+    Unit { names: &["C", "°C", "celsius"], dimension: Dimension::Temperature, factor: 1.0, offset: 273.15 },
+    Unit { names: &["F", "°F", "fahrenheit"], dimension: Dimension::Temperature, factor: 5.0 / 9.0, offset: 459.67 * 5.0 / 9.0 },
+    Unit { names: &["K", "kelvin"], dimension: Dimension::Temperature, factor: 1.0, offset: 0.0 },
+];
+
+/// Look up a unit; exact spelling wins so that e.g. `MB` and `mb` stay distinguishable from `m`
+fn find_unit(name: &str) -> Option<&'static Unit> {
+    let name = name.trim();
+    UNITS.iter()
+        .find(|unit| unit.names.contains(&name))
+        .or_else(|| UNITS.iter().find(|unit| unit.names.iter().any(|candidate| candidate.eq_ignore_ascii_case(name))))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Token {
+    Number(f64),
+    Operator(char),
+    Open,
+    Close,
+}
+
+fn tokenize(input: &str) -> Option<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '0'..='9' | '.' => {
+                let mut number = String::new();
+                while let Some(&digit) = chars.peek() {
+                    if digit.is_ascii_digit() || digit == '.' {
+                        number.push(digit);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push(Token::Number(number.parse().ok()?));
+            }
+            '+' | '-' | '*' | '/' | '^' => {
+                tokens.push(Token::Operator(c));
+                chars.next();
+            }
+            '×' => {
+                tokens.push(Token::Operator('*'));
+                chars.next();
+            }
+            '÷' => {
+                tokens.push(Token::Operator('/'));
+                chars.next();
+            }
+            '(' => {
+                tokens.push(Token::Open);
+                chars.next();
+            }
+            ')' => {
+                tokens.push(Token::Close);
+                chars.next();
+            }
+            _ => return None,
+        }
+    }
+
+    Some(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<Token> {
+        self.tokens.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.peek();
+        self.position += 1;
+        token
+    }
+
+    // expression = term (("+" | "-") term)*
+    fn expression(&mut self) -> Option<f64> {
+        let mut value = self.term()?;
+        while let Some(Token::Operator(op @ ('+' | '-'))) = self.peek() {
+            self.next();
+            let rhs = self.term()?;
+            value = if op == '+' { value + rhs } else { value - rhs };
+        }
+        Some(value)
+    }
+
+    // term = unary (("*" | "/") unary)*
+    fn term(&mut self) -> Option<f64> {
+        let mut value = self.unary()?;
+        while let Some(Token::Operator(op @ ('*' | '/'))) = self.peek() {
+            self.next();
+            let rhs = self.unary()?;
+            if op == '/' && rhs == 0.0 {
+                return None;
+            }
+            value = if op == '*' { value * rhs } else { value / rhs };
+        }
+        Some(value)
+    }
+
+    // unary = ("+" | "-") unary | power
+    fn unary(&mut self) -> Option<f64> {
+        match self.peek() {
+            Some(Token::Operator('-')) => {
+                self.next();
+                self.nested(|parser| parser.unary()).map(|value| -value)
+            }
+            Some(Token::Operator('+')) => {
+                self.next();
+                self.nested(|parser| parser.unary())
+            }
+            _ => self.power(),
+        }
+    }
+
+    // power = primary ("^" unary)?, right associative
+    fn power(&mut self) -> Option<f64> {
+        let base = self.primary()?;
+        if let Some(Token::Operator('^')) = self.peek() {
+            self.next();
+            let exponent = self.nested(|parser| parser.unary())?;
+            return Some(base.powf(exponent));
+        }
+        Some(base)
+    }
+
+    // primary = number | "(" expression ")"
+    fn primary(&mut self) -> Option<f64> {
+        match self.next()? {
+            Token::Number(value) => Some(value),
+            Token::Open => {
+                let value = self.nested(|parser| parser.expression())?;
+                match self.next()? {
+                    Token::Close => Some(value),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn nested(&mut self, parse: impl FnOnce(&mut Self) -> Option<f64>) -> Option<f64> {
+        if self.depth >= MAX_NESTING_DEPTH {
+            return None;
+        }
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+}
+
+/// Evaluate an arithmetic expression made of numbers, `+ - * / ^` and parentheses
+fn evaluate_arithmetic(input: &str) -> Option<f64> {
+    let tokens = tokenize(input)?;
+    if tokens.is_empty() {
+        return None;
+    }
+
+    let mut parser = Parser { tokens, position: 0, depth: 0 };
+    let value = parser.expression()?;
+    // Trailing tokens mean the input was not a single expression
+    if parser.position != parser.tokens.len() || !value.is_finite() {
+        return None;
+    }
+    Some(value)
+}
+
+/// Whether the input contains an actual operation rather than just a number
+/// A lone minus does not count, so dates (`2024-01-15`, `12/31/2024`) and phone numbers stay plain
+fn has_operation(input: &str) -> bool {
+    let looks_like_date = input.chars().all(|c| c.is_ascii_digit() || matches!(c, '/' | '-' | '.'))
+        && input.matches(['/', '-']).count() >= 2;
+    if looks_like_date {
+        return false;
+    }
+
+    input.contains(['*', '/', '^', '(', '×', '÷'])
+        || input.trim_start().trim_start_matches(['+', '-']).contains('+')
+        || input.contains(" - ")
+}
+
+/// Split `<expression> <unit> to|in|as <unit>` into its parts
+fn split_conversion(input: &str) -> Option<(&str, &'static Unit, &'static Unit)> {
+    for separator in [" to ", " in ", " as "] {
+        // The last separator splits, so `72 in to cm` keeps `in` as the source unit
+        let lower = input.to_ascii_lowercase();
+        let Some(index) = lower.rfind(separator) else {
+            continue;
+        };
+        let (left, right) = (&input[..index], &input[index + separator.len()..]);
+        let Some(to) = find_unit(right) else {
+            continue;
+        };
+
+        // The source unit is the trailing run of non-numeric characters, with or without a space
+        let left = left.trim_end();
+        let unit_start = left
+            .rfind(|c: char| c.is_ascii_digit() || c == ')' || c == '.' || c.is_whitespace())
+            .map(|index| index + left[index..].chars().next().map_or(1, char::len_utf8))
+            .unwrap_or(0);
+        let (expression, unit_name) = left.split_at(unit_start);
+        let Some(from) = find_unit(unit_name) else {
+            continue;
+        };
+
+        if from.dimension == to.dimension && !expression.trim().is_empty() {
+            return Some((expression, from, to));
+        }
+    }
+    None
+}
+
+/// Format a result without float noise, e.g. `119` instead of `118.99999999999999`
+fn format_number(value: f64) -> String {
+    let rounded = format!("{:.10}", value);
+    let trimmed = rounded.trim_end_matches('0').trim_end_matches('.');
+    if trimmed == "-0" { "0".to_string() } else { trimmed.to_string() }
+}
+
+/// Evaluate copied text as a unit conversion or arithmetic expression
+/// Returns None for anything that is not a calculation, including plain numbers
+pub fn evaluate_calculation(input: &str) -> Option<CalculationResult> {
+    let input = input.trim();
+    if input.is_empty() || input.len() > MAX_EXPRESSION_LENGTH || input.contains('\n') {
+        return None;
+    }
+
+    if let Some((expression, from, to)) = split_conversion(input) {
+        let value = evaluate_arithmetic(expression)?;
+        let base = value * from.factor + from.offset;
+        let converted = (base - to.offset) / to.factor;
+        if !converted.is_finite() {
+            return None;
+        }
+        let unit = to.names[0].to_string();
+        return Some(CalculationResult {
+            expression: input.to_string(),
+            value: converted,
+            display: format!("{} {}", format_number(converted), unit),
+            unit: Some(unit),
+        });
+    }
+
+    if !has_operation(input) {
+        return None;
+    }
+    let value = evaluate_arithmetic(input)?;
+    Some(CalculationResult {
+        expression: input.to_string(),
+        value,
+        display: format_number(value),
+        unit: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value(input: &str) -> Option<f64> {
+        evaluate_calculation(input).map(|result| result.value)
+    }
+
+    fn display(input: &str) -> Option<String> {
+        evaluate_calculation(input).map(|result| result.display)
+    }
+
+    #[test]
+    fn respects_operator_precedence() {
+        assert_eq!(value("2 + 3 * 4"), Some(14.0));
+        assert_eq!(value("(2 + 3) * 4"), Some(20.0));
+        assert_eq!(value("10 - 4 - 3"), Some(3.0));
+        assert_eq!(value("2 * 3 ^ 2"), Some(18.0));
+    }
+
+    #[test]
+    fn applies_unary_minus() {
+        assert_eq!(value("-2 ^ 2"), Some(-4.0));
+        assert_eq!(value("2 * -3"), Some(-6.0));
+        assert_eq!(value("-(1 + 2) * 2"), Some(-6.0));
+    }
+
+    #[test]
+    fn power_is_right_associative() {
+        assert_eq!(value("2 ^ 3 ^ 2"), Some(512.0));
+        assert_eq!(value("(2 ^ 3) ^ 2"), Some(64.0));
+    }
+
+    #[test]
+    fn division_by_zero_is_not_a_result() {
+        assert_eq!(evaluate_calculation("1 / 0"), None);
+        assert_eq!(evaluate_calculation("5 / (2 - 2)"), None);
+    }
+
+    #[test]
+    fn evaluates_documented_examples() {
+        assert_eq!(display("(1200*1.19)/12").as_deref(), Some("119"));
+
+        let inches = evaluate_calculation("72 in to cm").unwrap();
+        assert_eq!(inches.display, "182.88 cm");
+        assert_eq!(inches.unit.as_deref(), Some("cm"));
+
+        assert_eq!(display("3 GiB in MB").as_deref(), Some("3221.225472 MB"));
+    }
+
+    #[test]
+    fn dates_and_phone_numbers_stay_plain_text() {
+        for input in ["2024-01-05", "12/31/2024", "05.01.2024", "555-123-4567", "+49 30 1234567", "(555) 123-4567", "42", "-7"] {
+            assert_eq!(evaluate_calculation(input), None, "{} was read as a calculation", input);
+        }
+    }
+
+    #[test]
+    fn rejects_units_of_different_dimensions() {
+        assert_eq!(evaluate_calculation("5 kg to cm"), None);
+    }
+}
@@ -1,44 +1,34 @@
-use tauri_plugin_clipboard_manager::ClipboardExt;
-use tauri::image::Image;
-use crate::features::clipboard::{types::*, utils::*};
+use tauri::{Emitter, State};
+use crate::features::clipboard::{backend::*, calculator::*, diff::*, rules::*, source_app::*, store::*, types::*, utils::*, worker::ClipboardWorker};
 use base64::{Engine as _, engine::general_purpose};
 
 #[tauri::command]
-pub async fn copy_to_clipboard(app: tauri::AppHandle, text: String) -> Result<(), String> {
-    app.clipboard()
-        .write_text(text)
-        .map_err(|e| format!("Failed to copy to clipboard: {}", e))
+pub async fn copy_to_clipboard(worker: State<'_, ClipboardWorker>, text: String) -> Result<(), String> {
+    worker.run(move |backend| backend.write_text(&text)).await
 }
 
 #[tauri::command]
-pub async fn copy_image_to_clipboard(app: tauri::AppHandle, image_data: String) -> Result<(), String> {
-    // Decode base64 image data
-    let image_bytes = general_purpose::STANDARD.decode(&image_data)
-        .map_err(|e| format!("Failed to decode base64 image: {}", e))?;
-    
-    // Load image using the image crate
-    let img = image::load_from_memory(&image_bytes)
-        .map_err(|e| format!("Failed to load image: {}", e))?;
-    
-    // Convert to RGBA format for clipboard
-    let rgba_img = img.to_rgba8();
-    let (width, height) = rgba_img.dimensions();
-    
-    // Write image to clipboard using the Image struct
-    let image_data = rgba_img.into_raw();
-    let image = Image::new(&image_data, width, height);
-    
-    app.clipboard()
-        .write_image(&image)
-        .map_err(|e| format!("Failed to copy image to clipboard: {}", e))
+pub async fn copy_image_to_clipboard(worker: State<'_, ClipboardWorker>, image_data: String) -> Result<(), String> {
+    worker.run(move |backend| {
+        // Decode base64 image data
+        let image_bytes = general_purpose::STANDARD.decode(&image_data)
+            .map_err(|e| format!("Failed to decode base64 image: {}", e))?;
+        
+        // Load image using the image crate, stripping EXIF and other metadata
+        let img = decode_image_without_metadata(&image_bytes)?;
+        
+        write_clipboard_image(backend, &img)
+    }).await
 }
 
 #[tauri::command]
-pub async fn read_image_from_clipboard(app: tauri::AppHandle) -> Result<Option<String>, String> {
-    match app.clipboard().read_image() {
+pub async fn read_image_from_clipboard(worker: State<'_, ClipboardWorker>) -> Result<Option<String>, String> {
+    match worker.read_image().await {
         Ok(image) => {
-            // Convert image data to base64
-            let base64_data = general_purpose::STANDARD.encode(image.rgba());
+            // Convert image data to base64 off the async runtime
+            let base64_data = tauri::async_runtime::spawn_blocking(move || general_purpose::STANDARD.encode(&image.rgba))
+                .await
+                .map_err(|e| format!("Failed to encode clipboard image: {}", e))?;
             Ok(Some(base64_data))
         }
         Err(_) => Ok(None), // No image available
@@ -46,543 +36,911 @@ pub async fn read_image_from_clipboard(app: tauri::AppHandle) -> Result<Option<S
 }
 
 #[tauri::command]
-pub async fn copy_html_to_clipboard(app: tauri::AppHandle, html_content: String) -> Result<(), String> {
-    // For HTML content, we'll store it as text for now since tauri-plugin-clipboard-manager
-    // doesn't have direct HTML support yet, but we can detect and handle it
-    app.clipboard()
-        .write_text(html_content)
-        .map_err(|e| format!("Failed to copy HTML to clipboard: {}", e))
+pub async fn copy_html_to_clipboard(worker: State<'_, ClipboardWorker>, html_content: String) -> Result<(), String> {
+    // The markup is also offered as plain text so text-only targets keep receiving it
+    worker.run(move |backend| backend.write_html(&html_content, None)).await
 }
 
 #[tauri::command]
-pub async fn copy_rtf_to_clipboard(app: tauri::AppHandle, rtf_content: String) -> Result<(), String> {
+pub async fn copy_rtf_to_clipboard(worker: State<'_, ClipboardWorker>, rtf_content: String) -> Result<(), String> {
     // For RTF content, we'll store it as text for now
-    app.clipboard()
-        .write_text(rtf_content)
-        .map_err(|e| format!("Failed to copy RTF to clipboard: {}", e))
+    worker.run(move |backend| backend.write_text(&rtf_content)).await
 }
 
 #[tauri::command]
-pub async fn copy_files_to_clipboard(app: tauri::AppHandle, file_paths: Vec<String>) -> Result<(), String> {
-    // Convert file paths to a text representation
-    let file_list = file_paths.join("\n");
-    app.clipboard()
-        .write_text(file_list)
-        .map_err(|e| format!("Failed to copy files to clipboard: {}", e))
+pub async fn copy_files_to_clipboard(worker: State<'_, ClipboardWorker>, file_paths: Vec<String>) -> Result<(), String> {
+    worker.run(move |backend| backend.write_files(&file_paths)).await
 }
 
 #[tauri::command]
-pub async fn read_all_clipboard_formats(app: tauri::AppHandle) -> Result<std::collections::HashMap<String, String>, String> {
-    let mut formats = std::collections::HashMap::new();
-    
-    // Try to read text
-    if let Ok(text) = app.clipboard().read_text() {
-        if !text.is_empty() {
+pub async fn read_all_clipboard_formats(worker: State<'_, ClipboardWorker>) -> Result<std::collections::HashMap<String, String>, String> {
+    worker.run(|backend| {
+        let mut formats = std::collections::HashMap::new();
+        
+        // Try to read text
+        if let Ok(text) = backend.read_text() && !text.is_empty() {
             formats.insert("text/plain".to_string(), text.clone());
             
-            // Check if it's HTML
-            if text.contains("<html>") || text.contains("<!DOCTYPE") || text.contains("<") {
-                formats.insert("text/html".to_string(), text.clone());
-            }
-            
             // Check if it's RTF
             if text.starts_with("{\\rtf") {
                 formats.insert("text/rtf".to_string(), text.clone());
             }
-            
-            // Check if it's a file list (improved detection without disk I/O)
-            if is_file_list(&text) {
-                formats.insert("text/uri-list".to_string(), text.clone());
-            }
         }
+        
+        // Try to read HTML
+        if let Ok(html) = backend.read_html() {
+            formats.insert("text/html".to_string(), html);
+        }
+        
+        // Try to read a file list
+        if let Ok(files) = backend.read_files() {
+            formats.insert("text/uri-list".to_string(), files.join("\n"));
+        }
+        
+        // Try to read image
+        if let Ok(image) = backend.read_image() {
+            let base64_data = general_purpose::STANDARD.encode(&image.rgba);
+            formats.insert("image/png".to_string(), base64_data);
+        }
+        
+        Ok(formats)
+    }).await
+}
+
+#[tauri::command]
+pub async fn paste_from_clipboard(worker: State<'_, ClipboardWorker>) -> Result<String, String> {
+    worker.read_text().await
+}
+
+#[tauri::command]
+pub async fn get_clipboard_formats(worker: State<'_, ClipboardWorker>) -> Result<Vec<ClipboardFormat>, String> {
+    worker.run(|backend| Ok(detect_clipboard_formats(backend))).await
+}
+
+#[tauri::command]
+pub async fn add_to_clipboard_history(
+    app: tauri::AppHandle,
+    worker: State<'_, ClipboardWorker>,
+    content: String,
+) -> Result<(), String> {
+    match worker.run(move |backend| Ok(record_text_in_history(backend, &content))).await? {
+        Some(delta) => emit_history_update(&app, &delta),
+        None => Ok(()),
     }
+}
+
+#[tauri::command]
+pub async fn get_clipboard_history(
+    _app: tauri::AppHandle,
+    source_app: Option<String>,
+) -> Result<Vec<ClipboardHistoryItem>, String> {
+    let storage = get_clipboard_history_storage();
     
-    // Try to read image
-    if let Ok(image) = app.clipboard().read_image() {
-        let base64_data = general_purpose::STANDARD.encode(image.rgba());
-        formats.insert("image/png".to_string(), base64_data);
+    if let Ok(history) = storage.lock() {
+        // Optionally keep only items copied from the given application
+        let now = chrono::Utc::now().timestamp();
+        Ok(history.iter()
+            .filter(|item| !is_expired(item, now))
+            .filter(|item| match &source_app {
+                Some(query) => item.source_app.as_ref().is_some_and(|app| source_app_matches(app, query)),
+                None => true,
+            })
+            .cloned()
+            .map(redact_hidden_content)
+            .collect())
+    } else {
+        Ok(Vec::new())
     }
-    
-    Ok(formats)
 }
 
+/// Identify the application that currently owns the clipboard (X11 and XWayland only)
 #[tauri::command]
-pub async fn paste_from_clipboard(app: tauri::AppHandle) -> Result<String, String> {
-    app.clipboard()
-        .read_text()
-        .map_err(|e| format!("Failed to read from clipboard: {}", e))
+pub async fn get_clipboard_source_app(worker: State<'_, ClipboardWorker>) -> Result<Option<ClipboardSourceApp>, String> {
+    worker.run(|_| Ok(detect_source_app())).await
 }
 
 #[tauri::command]
-pub async fn get_clipboard_formats(app: tauri::AppHandle) -> Result<Vec<ClipboardFormat>, String> {
+pub async fn monitor_clipboard_changes(
+    app: tauri::AppHandle,
+    worker: State<'_, ClipboardWorker>,
+) -> Result<(), String> {
+    let capture = worker.run(|backend| Ok(capture_clipboard(backend))).await?;
+    for delta in &capture.history {
+        emit_history_update(&app, delta)?;
+    }
+    if let Some(state) = &capture.paste_stack {
+        emit_paste_stack_update(&app, state)?;
+    }
+    Ok(())
+}
 
-    use std::sync::Mutex;
-    use std::time::{Duration, Instant};
-    
-    static CACHE: Mutex<Option<(Vec<ClipboardFormat>, Instant)>> = Mutex::new(None);
-    const CACHE_DURATION: Duration = Duration::from_secs(5);
-    
-    // Check cache
-    {
-        let cache = CACHE.lock().unwrap();
-        if let Some((cached_formats, timestamp)) = &*cache {
-            if timestamp.elapsed() < CACHE_DURATION {
-                return Ok(cached_formats.clone());
-            }
-        }
+#[tauri::command]
+pub async fn get_clipboard_history_generation() -> Result<u64, String> {
+    Ok(current_history_generation())
+}
+
+/// Aggregate statistics over the clipboard history and the capture counters
+#[tauri::command]
+pub async fn get_clipboard_history_stats(top: Option<usize>) -> Result<ClipboardHistoryStats, String> {
+    compute_history_stats(top.unwrap_or(10))
+}
+
+#[tauri::command]
+pub async fn clear_clipboard_history(app: tauri::AppHandle) -> Result<(), String> {
+    let mut delta = None;
+    let storage = get_clipboard_history_storage();
+    if let Ok(mut history) = storage.lock() {
+        let removed = history.drain(..).map(|item| item.id).collect();
+        delta = Some(history_delta(Vec::new(), removed, Vec::new()));
     }
-    let mut formats = Vec::new();
-    
-    // Check for image data first
-    if let Ok(image) = app.clipboard().read_image() {
-        let base64_data = general_purpose::STANDARD.encode(image.rgba());
-        
-        formats.push(ClipboardFormat {
-            format_name: "Image (RGBA)".to_string(),
-            format_type: "image/rgba".to_string(),
-            data_size: image.rgba().len(),
-            content_preview: format!("{}x{} pixels", image.width(), image.height()),
-            is_available: true,
-            raw_data: Some(base64_data.clone()),
-        });
-        
-        formats.push(ClipboardFormat {
-            format_name: "Image (PNG)".to_string(),
-            format_type: "image/png".to_string(),
-            data_size: image.rgba().len(),
-            content_preview: "PNG format available".to_string(),
-            is_available: true,
-            raw_data: Some(base64_data.clone()),
-        });
-        
-        formats.push(ClipboardFormat {
-            format_name: "Image (JPEG)".to_string(),
-            format_type: "image/jpeg".to_string(),
-            data_size: image.rgba().len(),
-            content_preview: "JPEG format available".to_string(),
-            is_available: true,
-            raw_data: Some(base64_data.clone()),
-        });
-        
-        formats.push(ClipboardFormat {
-            format_name: "Image (Bitmap)".to_string(),
-            format_type: "image/bmp".to_string(),
-            data_size: image.rgba().len(),
-            content_preview: "Bitmap format available".to_string(),
-            is_available: true,
-            raw_data: Some(base64_data),
-        });
+
+    match delta {
+        Some(delta) => emit_history_update(&app, &delta),
+        None => Ok(()),
     }
-    
-    // Try to get text format and analyze all possible text-based formats
-    if let Ok(text_content) = app.clipboard().read_text() {
-        if !text_content.is_empty() {
-            // Plain text format (available on all platforms)
-            formats.push(ClipboardFormat {
-                format_name: "Plain Text".to_string(),
-                format_type: "text/plain".to_string(),
-                data_size: text_content.len(),
-                content_preview: if text_content.len() > 100 {
-                    format!("{}...", &text_content[..100])
-                } else {
-                    text_content.clone()
-                },
-                is_available: true,
-                raw_data: Some(general_purpose::STANDARD.encode(&text_content)),
-            });
-
-            // Unicode text (cross-platform)
-            formats.push(ClipboardFormat {
-                format_name: "Unicode Text (UTF-8)".to_string(),
-                format_type: "text/unicode".to_string(),
-                data_size: text_content.chars().count() * 4,
-                content_preview: "Unicode version of text".to_string(),
-                is_available: true,
-                raw_data: Some(general_purpose::STANDARD.encode(&text_content)),
-            });
-
-            // UTF-16 representation
-            let utf16_bytes: Vec<u8> = text_content.encode_utf16()
-                .flat_map(|c| c.to_le_bytes().to_vec())
-                .collect();
-            formats.push(ClipboardFormat {
-                format_name: "Unicode Text (UTF-16LE)".to_string(),
-                format_type: "text/utf-16le".to_string(),
-                data_size: utf16_bytes.len(),
-                content_preview: "UTF-16 Little Endian encoding".to_string(),
-                is_available: true,
-                raw_data: Some(general_purpose::STANDARD.encode(&utf16_bytes)),
-            });
-
-            // HTML detection and format
-            if text_content.contains("<html>") || text_content.contains("<!DOCTYPE") || 
-               text_content.contains("<div") || text_content.contains("<p") ||
-               text_content.contains("<span") || text_content.contains("<br") {
-                formats.push(ClipboardFormat {
-                    format_name: "HTML".to_string(),
-                    format_type: "text/html".to_string(),
-                    data_size: text_content.len(),
-                    content_preview: "HTML content detected".to_string(),
-                    is_available: true,
-                    raw_data: Some(general_purpose::STANDARD.encode(&text_content)),
-                });
-                
-                // HTML Fragment (Windows specific)
-                formats.push(ClipboardFormat {
-                    format_name: "HTML Fragment".to_string(),
-                    format_type: "text/html-fragment".to_string(),
-                    data_size: text_content.len(),
-                    content_preview: "HTML Fragment format".to_string(),
-                    is_available: true,
-                    raw_data: Some(general_purpose::STANDARD.encode(&text_content)),
-                });
-            }
+}
 
-            // RTF (Rich Text Format) detection
-            if text_content.starts_with("{\\rtf") {
-                formats.push(ClipboardFormat {
-                    format_name: "Rich Text Format".to_string(),
-                    format_type: "text/rtf".to_string(),
-                    data_size: text_content.len(),
-                    content_preview: "Rich Text Format content".to_string(),
-                    is_available: true,
-                    raw_data: Some(general_purpose::STANDARD.encode(&text_content)),
-                });
-            }
+#[tauri::command]
+pub async fn clear_clipboard(worker: State<'_, ClipboardWorker>) -> Result<(), String> {
+    worker.run(|backend| backend.clear()).await
+}
 
-            // URL detection
-            if text_content.starts_with("http://") || text_content.starts_with("https://") ||
-               text_content.starts_with("ftp://") || text_content.starts_with("ftps://") {
-                formats.push(ClipboardFormat {
-                    format_name: "URL".to_string(),
-                    format_type: "text/uri-list".to_string(),
-                    data_size: text_content.len(),
-                    content_preview: "URL format".to_string(),
-                    is_available: true,
-                    raw_data: Some(general_purpose::STANDARD.encode(&text_content)),
-                });
-                
-                formats.push(ClipboardFormat {
-                    format_name: "Internet Shortcut".to_string(),
-                    format_type: "application/x-mswinurl".to_string(),
-                    data_size: text_content.len(),
-                    content_preview: "Windows Internet Shortcut".to_string(),
-                    is_available: true,
-                    raw_data: Some(general_purpose::STANDARD.encode(&text_content)),
-                });
-            }
+/// Load the image an image operation works on: a history item if an ID is given, otherwise the clipboard
+fn load_operation_image(backend: &dyn ClipboardBackend, item_id: Option<String>) -> Result<image::DynamicImage, String> {
+    match item_id {
+        Some(id) => load_history_image(&id),
+        None => read_clipboard_image(backend),
+    }
+}
 
-            // File path detection (improved without disk I/O)
-            if is_file_list(&text_content) {
-                let lines: Vec<&str> = text_content.lines().filter(|line| !line.trim().is_empty()).collect();
-                formats.push(ClipboardFormat {
-                    format_name: "File Drop List".to_string(),
-                    format_type: "text/uri-list".to_string(),
-                    data_size: text_content.len(),
-                    content_preview: format!("{} file(s)", lines.len()),
-                    is_available: true,
-                    raw_data: Some(general_purpose::STANDARD.encode(&text_content)),
-                });
-                
-                formats.push(ClipboardFormat {
-                    format_name: "Shell IDList Array".to_string(),
-                    format_type: "application/x-shell-idlist".to_string(),
-                    data_size: text_content.len(),
-                    content_preview: "Windows Shell file list".to_string(),
-                    is_available: true,
-                    raw_data: Some(general_purpose::STANDARD.encode(&text_content)),
-                });
-            }
+/// Write the result of an image operation back to the clipboard and describe it for the frontend
+fn finish_image_operation(backend: &dyn ClipboardBackend, img: &image::DynamicImage) -> Result<ImageOperationResult, String> {
+    write_clipboard_image(backend, img)?;
+
+    let png_data = encode_image(img, image::ImageFormat::Png, None)?;
+    Ok(ImageOperationResult {
+        image_data: general_purpose::STANDARD.encode(&png_data),
+        image_format: "png".to_string(),
+        image_dimensions: (img.width(), img.height()),
+        data_size: png_data.len(),
+    })
+}
 
-            // JSON detection
-            if (text_content.trim().starts_with("{") && text_content.trim().ends_with("}")) ||
-               (text_content.trim().starts_with("[") && text_content.trim().ends_with("]")) {
-                if serde_json::from_str::<serde_json::Value>(&text_content).is_ok() {
-                    formats.push(ClipboardFormat {
-                        format_name: "JSON".to_string(),
-                        format_type: "application/json".to_string(),
-                        data_size: text_content.len(),
-                        content_preview: "Valid JSON data".to_string(),
-                        is_available: true,
-                        raw_data: Some(general_purpose::STANDARD.encode(&text_content)),
-                    });
-                }
-            }
+/// Resize the clipboard image (or a history item) and write the result to the clipboard
+#[tauri::command]
+pub async fn resize_clipboard_image(
+    worker: State<'_, ClipboardWorker>,
+    item_id: Option<String>,
+    width: u32,
+    height: u32,
+    fit: Option<ImageFitMode>,
+) -> Result<ImageOperationResult, String> {
+    worker.run(move |backend| {
+        let img = load_operation_image(backend, item_id)?;
+        let resized = resize_image(&img, width, height, fit.unwrap_or(ImageFitMode::Contain))?;
+        finish_image_operation(backend, &resized)
+    }).await
+}
 
-            // XML detection
-            if text_content.trim().starts_with("<?xml") || 
-               (text_content.trim().starts_with("<") && text_content.trim().ends_with(">")) {
-                formats.push(ClipboardFormat {
-                    format_name: "XML".to_string(),
-                    format_type: "application/xml".to_string(),
-                    data_size: text_content.len(),
-                    content_preview: "XML content detected".to_string(),
-                    is_available: true,
-                    raw_data: Some(general_purpose::STANDARD.encode(&text_content)),
-                });
-            }
+/// Crop the clipboard image (or a history item) and write the result to the clipboard
+#[tauri::command]
+pub async fn crop_clipboard_image(
+    worker: State<'_, ClipboardWorker>,
+    item_id: Option<String>,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+) -> Result<ImageOperationResult, String> {
+    worker.run(move |backend| {
+        let img = load_operation_image(backend, item_id)?;
+        let cropped = crop_image(&img, x, y, width, height)?;
+        finish_image_operation(backend, &cropped)
+    }).await
+}
 
-            // CSV detection
-            if text_content.contains(",") && text_content.lines().count() > 1 {
-                let lines: Vec<&str> = text_content.lines().collect();
-                if lines.len() > 1 && lines.iter().all(|line| line.contains(",")) {
-                    formats.push(ClipboardFormat {
-                        format_name: "CSV".to_string(),
-                        format_type: "text/csv".to_string(),
-                        data_size: text_content.len(),
-                        content_preview: format!("CSV with {} rows", lines.len()),
-                        is_available: true,
-                        raw_data: Some(general_purpose::STANDARD.encode(&text_content)),
-                    });
-                }
-            }
+/// Rotate the clipboard image (or a history item) clockwise and write the result to the clipboard
+#[tauri::command]
+pub async fn rotate_clipboard_image(
+    worker: State<'_, ClipboardWorker>,
+    item_id: Option<String>,
+    degrees: u32,
+) -> Result<ImageOperationResult, String> {
+    worker.run(move |backend| {
+        let img = load_operation_image(backend, item_id)?;
+        let rotated = rotate_image(&img, degrees)?;
+        finish_image_operation(backend, &rotated)
+    }).await
+}
 
-            // Email detection
-            if text_content.contains("@") && text_content.contains(".") && 
-               !text_content.contains(" ") && text_content.lines().count() == 1 {
-                formats.push(ClipboardFormat {
-                    format_name: "Email Address".to_string(),
-                    format_type: "text/x-email".to_string(),
-                    data_size: text_content.len(),
-                    content_preview: "Email address format".to_string(),
-                    is_available: true,
-                    raw_data: Some(general_purpose::STANDARD.encode(&text_content)),
-                });
-            }
+/// Convert the clipboard image (or a history item) to grayscale and write the result to the clipboard
+#[tauri::command]
+pub async fn grayscale_clipboard_image(
+    worker: State<'_, ClipboardWorker>,
+    item_id: Option<String>,
+) -> Result<ImageOperationResult, String> {
+    worker.run(move |backend| {
+        let img = load_operation_image(backend, item_id)?;
+        finish_image_operation(backend, &img.grayscale())
+    }).await
+}
 
-            // Code detection (basic)
-            if text_content.contains("function") || text_content.contains("class") ||
-               text_content.contains("import") || text_content.contains("const") ||
-               text_content.contains("def ") || text_content.contains("public class") {
-                formats.push(ClipboardFormat {
-                    format_name: "Source Code".to_string(),
-                    format_type: "text/x-source-code".to_string(),
-                    data_size: text_content.len(),
-                    content_preview: "Programming code detected".to_string(),
-                    is_available: true,
-                    raw_data: Some(general_purpose::STANDARD.encode(&text_content)),
-                });
-            }
+/// Re-encode the clipboard image (or a history item) in another format
+/// The re-encoded pixels (including any lossy artifacts) are written back to the clipboard
+#[tauri::command]
+pub async fn reencode_clipboard_image(
+    worker: State<'_, ClipboardWorker>,
+    item_id: Option<String>,
+    format: String,
+    quality: Option<u8>,
+) -> Result<ImageOperationResult, String> {
+    worker.run(move |backend| {
+        let img = load_operation_image(backend, item_id)?;
+        let image_format = parse_image_format(&format)?;
+
+        let encoded = encode_image(&img, image_format, quality)?;
+        let reencoded = decode_image_without_metadata(&encoded)?;
+        write_clipboard_image(backend, &reencoded)?;
+
+        Ok(ImageOperationResult {
+            image_data: general_purpose::STANDARD.encode(&encoded),
+            image_format: image_format.extensions_str().first().copied().unwrap_or("png").to_string(),
+            image_dimensions: (reencoded.width(), reencoded.height()),
+            data_size: encoded.len(),
+        })
+    }).await
+}
 
-            // Markdown detection
-            if text_content.contains("# ") || text_content.contains("## ") ||
-               text_content.contains("**") || text_content.contains("```") {
-                formats.push(ClipboardFormat {
-                    format_name: "Markdown".to_string(),
-                    format_type: "text/markdown".to_string(),
-                    data_size: text_content.len(),
-                    content_preview: "Markdown content detected".to_string(),
-                    is_available: true,
-                    raw_data: Some(general_purpose::STANDARD.encode(&text_content)),
-                });
+/// Render the clipboard text (or a history item) as a QR code
+/// The QR code is written to the clipboard unless `write_to_clipboard` is false; the PNG is always returned
+#[tauri::command]
+pub async fn generate_qr_code(
+    worker: State<'_, ClipboardWorker>,
+    item_id: Option<String>,
+    size: Option<u32>,
+    error_correction: Option<String>,
+    write_to_clipboard: Option<bool>,
+) -> Result<ImageOperationResult, String> {
+    worker.run(move |backend| {
+        let content = match item_id {
+            Some(id) => {
+                let item = get_history_item(&id)?;
+                item.plain_text.unwrap_or(item.primary_content)
             }
+            None => backend.read_text()?,
+        };
 
-            // Base64 detection
-            if text_content.len() > 20 && text_content.chars().all(|c| {
-                c.is_ascii_alphanumeric() || c == '+' || c == '/' || c == '='
-            }) && text_content.len() % 4 == 0 {
-                formats.push(ClipboardFormat {
-                    format_name: "Base64 Encoded Data".to_string(),
-                    format_type: "application/base64".to_string(),
-                    data_size: text_content.len(),
-                    content_preview: "Base64 encoded content".to_string(),
-                    is_available: true,
-                    raw_data: Some(general_purpose::STANDARD.encode(&text_content)),
-                });
-            }
-        }
-    }
+        let qr_image = render_qr_code(&content, size.unwrap_or(256), error_correction.as_deref())?;
 
-    // Add system-specific formats
-    #[cfg(target_os = "windows")]
-    {
-        // Windows-specific formats would go here
-        // These are conceptual as tauri-plugin-clipboard-manager may not expose them directly
-        if !formats.is_empty() {
-            formats.push(ClipboardFormat {
-                format_name: "Windows Locale".to_string(),
-                format_type: "application/x-windows-locale".to_string(),
-                data_size: 4,
-                content_preview: "System locale information".to_string(),
-                is_available: true,
-                raw_data: None,
-            });
+        if write_to_clipboard.unwrap_or(true) {
+            return finish_image_operation(backend, &qr_image);
         }
-    }
 
-    #[cfg(target_os = "macos")]
-    {
-        // macOS-specific formats
-        if !formats.is_empty() {
-            formats.push(ClipboardFormat {
-                format_name: "macOS Pasteboard Type".to_string(),
-                format_type: "com.apple.pasteboard.promised-file-url".to_string(),
-                data_size: 0,
-                content_preview: "macOS pasteboard metadata".to_string(),
-                is_available: true,
-                raw_data: None,
-            });
-        }
-    }
+        let png_data = encode_image(&qr_image, image::ImageFormat::Png, None)?;
+        Ok(ImageOperationResult {
+            image_data: general_purpose::STANDARD.encode(&png_data),
+            image_format: "png".to_string(),
+            image_dimensions: (qr_image.width(), qr_image.height()),
+            data_size: png_data.len(),
+        })
+    }).await
+}
 
-    // If no formats detected, show empty state
-    if formats.is_empty() {
-        formats.push(ClipboardFormat {
-            format_name: "Empty".to_string(),
-            format_type: "empty".to_string(),
-            data_size: 0,
-            content_preview: "Clipboard is empty".to_string(),
-            is_available: false,
-            raw_data: None,
-        });
+/// Decode QR codes and barcodes from the clipboard image (or a history item)
+/// Every decoded value is added to the clipboard history
+#[tauri::command]
+pub async fn decode_clipboard_barcodes(
+    app: tauri::AppHandle,
+    worker: State<'_, ClipboardWorker>,
+    item_id: Option<String>,
+) -> Result<Vec<DecodedBarcode>, String> {
+    let (barcodes, deltas) = worker.run(move |backend| {
+        let img = load_operation_image(backend, item_id)?;
+        let barcodes = decode_barcodes(&img)?;
+        let deltas: Vec<_> = barcodes.iter()
+            .filter_map(|barcode| record_derived_text_in_history(&barcode.text))
+            .collect();
+        Ok((barcodes, deltas))
+    }).await?;
+
+    for delta in &deltas {
+        emit_history_update(&app, delta)?;
     }
 
-    Ok(formats)
+    Ok(barcodes)
 }
 
+/// Notify all windows that the clipboard history changed
+fn emit_history_update(app: &tauri::AppHandle, delta: &ClipboardHistoryDelta) -> Result<(), String> {
+    app.emit("clipboard-history-updated", delta)
+        .map_err(|e| format!("Failed to emit clipboard-history-updated event: {}", e))
+}
+
+/// Delete a single item from the clipboard history
 #[tauri::command]
-pub async fn add_to_clipboard_history(app: tauri::AppHandle, content: String) -> Result<(), String> {
+pub async fn delete_clipboard_history_item(app: tauri::AppHandle, id: String) -> Result<(), String> {
+    let delta = {
+        let storage = get_clipboard_history_storage();
+        let mut history = storage.lock().map_err(|e| e.to_string())?;
+        let index = history.iter()
+            .position(|item| item.id == id)
+            .ok_or_else(|| format!("Clipboard history item '{}' not found", id))?;
+        history.remove(index);
+        history_delta(Vec::new(), vec![id], Vec::new())
+    };
+
+    emit_history_update(&app, &delta)
+}
+
+/// Replace the text content of a clipboard history item
+/// Content type and the derived text formats are re-detected from the new text
+#[tauri::command]
+pub async fn update_clipboard_history_item(
+    app: tauri::AppHandle,
+    id: String,
+    content: String,
+) -> Result<ClipboardHistoryItem, String> {
     if content.is_empty() {
-        return Ok(());
+        return Err("Clipboard history item content cannot be empty".to_string());
     }
 
-    let timestamp = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC").to_string();
-    let content_type = detect_content_type(&content);
-    let id = generate_clipboard_id(&content, &timestamp);
-    
-    // Generate formats for this content
-    let formats = get_clipboard_formats(app.clone()).await.unwrap_or_default();
-    
-    // Extract different format types
-    let plain_text = if content_type != "Image" { Some(content.clone()) } else { None };
-    let html_content = if content.contains("<html>") || content.contains("<!DOCTYPE") || content.contains("<div") {
-        Some(content.clone())
-    } else { None };
-    let rtf_content = if content.starts_with("{\\rtf") {
-        Some(content.clone())
-    } else { None };
-    
-    // Check for file paths (improved detection)
-    let file_paths = if is_file_list(&content) {
-        Some(content.lines().filter(|line| !line.trim().is_empty()).map(|s| s.to_string()).collect())
-    } else { None };
-    
-    let file_list = if file_paths.is_some() { Some(content.clone()) } else { None };
-    
-    let history_item = ClipboardHistoryItem {
-        id,
-        timestamp,
-        formats,
-        primary_content: content.clone(),
-        content_type,
-        plain_text,
-        html_content,
-        rtf_content,
-        image_data: None,
-        image_format: None,
-        image_dimensions: None,
-        file_paths,
-        file_list,
-        custom_formats: None,
+    let (updated, delta) = {
+        let storage = get_clipboard_history_storage();
+        let mut history = storage.lock().map_err(|e| e.to_string())?;
+        let item = history.iter_mut()
+            .find(|item| item.id == id)
+            .ok_or_else(|| format!("Clipboard history item '{}' not found", id))?;
+        if item.content_type == "Image" {
+            return Err(format!("Clipboard history item '{}' is an image and has no text to edit", id));
+        }
+        set_text_content(item, &content);
+        (item.clone(), history_delta(Vec::new(), Vec::new(), vec![id]))
     };
 
-    let storage = get_clipboard_history_storage();
-    if let Ok(mut history) = storage.lock() {
-        // Check if this content already exists (avoid duplicates)
-        if !history.iter().any(|item| item.primary_content == content) {
-            // Add to front of history
-            history.push_front(history_item);
-            
-            // Keep only last 50 items
-            if history.len() > 50 {
-                history.pop_back();
+    emit_history_update(&app, &delta)?;
+    Ok(redact_hidden_content(updated))
+}
+
+/// Set or clear the label and note of a clipboard history item
+#[tauri::command]
+pub async fn annotate_clipboard_history_item(
+    app: tauri::AppHandle,
+    id: String,
+    label: Option<String>,
+    note: Option<String>,
+) -> Result<ClipboardHistoryItem, String> {
+    let (updated, delta) = {
+        let storage = get_clipboard_history_storage();
+        let mut history = storage.lock().map_err(|e| e.to_string())?;
+        let item = history.iter_mut()
+            .find(|item| item.id == id)
+            .ok_or_else(|| format!("Clipboard history item '{}' not found", id))?;
+        item.label = label.filter(|label| !label.trim().is_empty());
+        item.note = note.filter(|note| !note.trim().is_empty());
+        (item.clone(), history_delta(Vec::new(), Vec::new(), vec![id]))
+    };
+
+    emit_history_update(&app, &delta)?;
+    Ok(redact_hidden_content(updated))
+}
+
+/// Move a clipboard history item to a new position (0 is the most recent slot)
+#[tauri::command]
+pub async fn move_clipboard_history_item(
+    app: tauri::AppHandle,
+    id: String,
+    index: usize,
+) -> Result<(), String> {
+    let delta = {
+        let storage = get_clipboard_history_storage();
+        let mut history = storage.lock().map_err(|e| e.to_string())?;
+        let current = history.iter()
+            .position(|item| item.id == id)
+            .ok_or_else(|| format!("Clipboard history item '{}' not found", id))?;
+        let item = history.remove(current)
+            .ok_or_else(|| format!("Clipboard history item '{}' not found", id))?;
+        let target = index.min(history.len());
+        history.insert(target, item);
+        history_delta(Vec::new(), Vec::new(), vec![id])
+    };
+
+    emit_history_update(&app, &delta)
+}
+
+/// Merge several text items into one entry joined by `separator` (newline by default)
+/// The merged entry replaces the originals at the front of the history
+#[tauri::command]
+pub async fn merge_clipboard_history_items(
+    app: tauri::AppHandle,
+    mut ids: Vec<String>,
+    separator: Option<String>,
+) -> Result<ClipboardHistoryItem, String> {
+    // An ID listed twice names the same item, so keep its first position only
+    let mut seen = std::collections::HashSet::new();
+    ids.retain(|id| seen.insert(id.clone()));
+    if ids.len() < 2 {
+        return Err("At least two distinct clipboard history items are required to merge".to_string());
+    }
+
+    let separator = separator.unwrap_or_else(|| "\n".to_string());
+    let (merged, delta) = {
+        let storage = get_clipboard_history_storage();
+        let mut history = storage.lock().map_err(|e| e.to_string())?;
+
+        let mut parts = Vec::with_capacity(ids.len());
+        for id in &ids {
+            let item = history.iter()
+                .find(|item| &item.id == id)
+                .ok_or_else(|| format!("Clipboard history item '{}' not found", id))?;
+            if item.content_type == "Image" {
+                return Err(format!("Clipboard history item '{}' is an image and cannot be merged", id));
             }
+            parts.push(item.plain_text.clone().unwrap_or_else(|| item.primary_content.clone()));
         }
+        let content = parts.join(&separator);
+
+        let timestamp = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC").to_string();
+        let id = generate_clipboard_id(&content, &timestamp);
+        let merged = create_text_history_item(id, timestamp, Vec::new(), &content);
+
+        history.retain(|item| !ids.contains(&item.id));
+        let mut removed = ids;
+        removed.extend(push_history_item(&mut history, merged.clone()));
+        (merged.clone(), history_delta(vec![merged.id], removed, Vec::new()))
+    };
+
+    emit_history_update(&app, &delta)?;
+    Ok(redact_hidden_content(merged))
+}
+
+/// Pin or unpin a clipboard history item; pinned items are never evicted
+#[tauri::command]
+pub async fn pin_clipboard_history_item(
+    app: tauri::AppHandle,
+    id: String,
+    pinned: bool,
+) -> Result<ClipboardHistoryItem, String> {
+    let (updated, delta) = {
+        let storage = get_clipboard_history_storage();
+        let mut history = storage.lock().map_err(|e| e.to_string())?;
+        let item = history.iter_mut()
+            .find(|item| item.id == id)
+            .ok_or_else(|| format!("Clipboard history item '{}' not found", id))?;
+        item.pinned = pinned;
+        (item.clone(), history_delta(Vec::new(), Vec::new(), vec![id]))
+    };
+
+    emit_history_update(&app, &delta)?;
+    Ok(redact_hidden_content(updated))
+}
+
+/// Copy a secret (password, token, ...) to the clipboard without recording it in history
+/// The clipboard is cleared after `ttl_seconds`, but only if it still holds the secret
+#[tauri::command]
+pub async fn copy_secret(
+    worker: State<'_, ClipboardWorker>,
+    text: String,
+    ttl_seconds: u64,
+) -> Result<(), String> {
+    if text.is_empty() {
+        return Err("Secret cannot be empty".to_string());
     }
+    if ttl_seconds == 0 {
+        return Err("Secret TTL must be at least one second".to_string());
+    }
+
+    // Register first so a concurrent capture never sees an unregistered secret
+    let token = register_active_secret(&text);
+    if let Err(e) = worker.run(move |backend| backend.write_secret_text(&text)).await {
+        take_active_secret(token, None);
+        return Err(e);
+    }
+
+    let worker = worker.inner().clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_secs(ttl_seconds)).await;
+
+        // Compare and clear in one job so no other clipboard access can slip in between
+        let result = worker.run(move |backend| {
+            let current = backend.read_text().ok();
+            if take_active_secret(token, current.as_deref()) {
+                backend.clear()?;
+            }
+            Ok(())
+        }).await;
+        if let Err(e) = result {
+            eprintln!("Failed to clear secret from clipboard: {}", e);
+        }
+    });
 
     Ok(())
 }
 
+/// Collection as handed to the frontend, see `redact_hidden_content`
+fn redact_collection(mut collection: ClipboardCollection) -> ClipboardCollection {
+    collection.items = collection.items.into_iter().map(redact_hidden_content).collect();
+    collection
+}
+
+/// Find a collection by ID
+fn find_collection_mut<'a>(data: &'a mut ClipboardData, id: &str) -> Result<&'a mut ClipboardCollection, String> {
+    data.collections.iter_mut()
+        .find(|collection| collection.id == id)
+        .ok_or_else(|| format!("Clipboard collection '{}' not found", id))
+}
+
+/// Validate a collection name; names are unique regardless of case
+fn validate_collection_name(data: &ClipboardData, name: &str, except_id: Option<&str>) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Collection name cannot be empty".to_string());
+    }
+    let taken = data.collections.iter()
+        .any(|collection| Some(collection.id.as_str()) != except_id && collection.name.eq_ignore_ascii_case(name));
+    if taken {
+        return Err(format!("A collection named '{}' already exists", name));
+    }
+    Ok(name.to_string())
+}
+
+/// List all clipboard collections in their display order
 #[tauri::command]
-pub async fn get_clipboard_history(_app: tauri::AppHandle) -> Result<Vec<ClipboardHistoryItem>, String> {
-    let storage = get_clipboard_history_storage();
-    
-    if let Ok(history) = storage.lock() {
-        Ok(history.iter().cloned().collect())
-    } else {
-        Ok(Vec::new())
+pub async fn get_clipboard_collections() -> Result<Vec<ClipboardCollection>, String> {
+    read_clipboard_data(|data| data.collections.iter().cloned().map(redact_collection).collect())
+}
+
+/// Create an empty collection at the end of the collection list
+#[tauri::command]
+pub async fn create_clipboard_collection(name: String) -> Result<ClipboardCollection, String> {
+    update_clipboard_data(|data| {
+        let name = validate_collection_name(data, &name, None)?;
+        let collection = new_clipboard_collection(name);
+        data.collections.push(collection.clone());
+        Ok(collection)
+    })
+}
+
+#[tauri::command]
+pub async fn rename_clipboard_collection(id: String, name: String) -> Result<ClipboardCollection, String> {
+    update_clipboard_data(|data| {
+        let name = validate_collection_name(data, &name, Some(&id))?;
+        let collection = find_collection_mut(data, &id)?;
+        collection.name = name;
+        Ok(redact_collection(collection.clone()))
+    })
+}
+
+/// Delete a collection together with its items
+#[tauri::command]
+pub async fn delete_clipboard_collection(id: String) -> Result<(), String> {
+    update_clipboard_data(|data| {
+        let index = data.collections.iter()
+            .position(|collection| collection.id == id)
+            .ok_or_else(|| format!("Clipboard collection '{}' not found", id))?;
+        data.collections.remove(index);
+        Ok(())
+    })
+}
+
+/// Move a collection to a new position in the collection list
+#[tauri::command]
+pub async fn move_clipboard_collection(id: String, index: usize) -> Result<(), String> {
+    update_clipboard_data(|data| {
+        let current = data.collections.iter()
+            .position(|collection| collection.id == id)
+            .ok_or_else(|| format!("Clipboard collection '{}' not found", id))?;
+        let collection = data.collections.remove(current);
+        let target = index.min(data.collections.len());
+        data.collections.insert(target, collection);
+        Ok(())
+    })
+}
+
+/// Copy a history item into a collection, at `index` or at the end
+/// The item stays in the collection even after it is evicted from history
+#[tauri::command]
+pub async fn add_to_clipboard_collection(
+    collection_id: String,
+    item_id: String,
+    index: Option<usize>,
+) -> Result<ClipboardCollection, String> {
+    let item = get_history_item(&item_id)?;
+
+    update_clipboard_data(|data| {
+        let collection = find_collection_mut(data, &collection_id)?;
+        if collection.items.iter().any(|existing| existing.id == item.id) {
+            return Err(format!("Clipboard item '{}' is already in collection '{}'", item.id, collection.name));
+        }
+        let target = index.unwrap_or(collection.items.len()).min(collection.items.len());
+        collection.items.insert(target, item);
+        Ok(redact_collection(collection.clone()))
+    })
+}
+
+#[tauri::command]
+pub async fn remove_from_clipboard_collection(
+    collection_id: String,
+    item_id: String,
+) -> Result<ClipboardCollection, String> {
+    update_clipboard_data(|data| {
+        let collection = find_collection_mut(data, &collection_id)?;
+        let index = collection.items.iter()
+            .position(|item| item.id == item_id)
+            .ok_or_else(|| format!("Clipboard item '{}' is not in collection '{}'", item_id, collection.name))?;
+        collection.items.remove(index);
+        Ok(redact_collection(collection.clone()))
+    })
+}
+
+/// Move an item within a collection, or into another collection when `target_collection_id` is given
+#[tauri::command]
+pub async fn move_clipboard_collection_item(
+    collection_id: String,
+    item_id: String,
+    index: usize,
+    target_collection_id: Option<String>,
+) -> Result<ClipboardCollection, String> {
+    update_clipboard_data(|data| {
+        let source = find_collection_mut(data, &collection_id)?;
+        let current = source.items.iter()
+            .position(|item| item.id == item_id)
+            .ok_or_else(|| format!("Clipboard item '{}' is not in collection '{}'", item_id, source.name))?;
+        let item = source.items.remove(current);
+
+        let target = find_collection_mut(data, target_collection_id.as_deref().unwrap_or(&collection_id))?;
+        if target.items.iter().any(|existing| existing.id == item.id) {
+            return Err(format!("Clipboard item '{}' is already in collection '{}'", item.id, target.name));
+        }
+        let position = index.min(target.items.len());
+        target.items.insert(position, item);
+        Ok(redact_collection(target.clone()))
+    })
+}
+
+/// Notify all windows that the paste stack changed
+fn emit_paste_stack_update(app: &tauri::AppHandle, state: &PasteStackState) -> Result<(), String> {
+    app.emit("paste-stack-updated", state)
+        .map_err(|e| format!("Failed to emit paste-stack-updated event: {}", e))
+}
+
+/// Turn on collect mode: every following copy picked up by `monitor_clipboard_changes` is appended to the paste stack
+#[tauri::command]
+pub async fn start_collect_mode(
+    app: tauri::AppHandle,
+    worker: State<'_, ClipboardWorker>,
+) -> Result<PasteStackState, String> {
+    // Whatever is on the clipboard already was copied before collect mode started
+    let state = worker.run(|backend| start_paste_stack(backend.read_text().ok())).await?;
+    emit_paste_stack_update(&app, &state)?;
+    Ok(state)
+}
+
+/// Turn off collect mode and return the collected items
+#[tauri::command]
+pub async fn stop_collect_mode(app: tauri::AppHandle) -> Result<PasteStackState, String> {
+    let collected = stop_paste_stack()?;
+    emit_paste_stack_update(&app, &PasteStackState { active: false, items: Vec::new() })?;
+    Ok(collected)
+}
+
+#[tauri::command]
+pub async fn get_paste_stack() -> Result<PasteStackState, String> {
+    paste_stack_state()
+}
+
+/// Put the whole paste stack on the clipboard as one value joined by `separator` (newline by default)
+#[tauri::command]
+pub async fn copy_paste_stack(
+    worker: State<'_, ClipboardWorker>,
+    separator: Option<String>,
+) -> Result<String, String> {
+    let separator = separator.unwrap_or_else(|| "\n".to_string());
+    worker.run(move |backend| {
+        let joined = join_paste_stack(&separator)?;
+        backend.write_text(&joined)?;
+        Ok(joined)
+    }).await
+}
+
+/// Restore the next paste stack item to the clipboard, oldest first unless `order` is LIFO
+/// Returns None once the stack is empty
+#[tauri::command]
+pub async fn pop_paste_stack(
+    app: tauri::AppHandle,
+    worker: State<'_, ClipboardWorker>,
+    order: Option<PasteStackOrder>,
+) -> Result<Option<String>, String> {
+    let order = order.unwrap_or(PasteStackOrder::Fifo);
+    let (item, state) = worker.run(move |backend| {
+        let (item, state) = pop_paste_stack_item(order)?;
+        if let Some(item) = &item {
+            backend.write_text(item)?;
+        }
+        Ok((item, state))
+    }).await?;
+
+    if item.is_some() {
+        emit_paste_stack_update(&app, &state)?;
     }
+    Ok(item)
 }
 
 #[tauri::command]
-pub async fn monitor_clipboard_changes(app: tauri::AppHandle) -> Result<(), String> {
-    // Check for image data first
-    if let Ok(image) = app.clipboard().read_image() {
-        let timestamp = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC").to_string();
-        let id = generate_clipboard_id("image_data", &timestamp);
-        
-        // Process image data
-        match process_image_data(image.rgba(), image.width() as u32, image.height() as u32) {
-            Ok((base64_data, format, dimensions)) => {
-                let formats = get_clipboard_formats(app.clone()).await.unwrap_or_default();
-                
-                let history_item = ClipboardHistoryItem {
-                    id,
-                    timestamp,
-                    formats,
-                    primary_content: format!("Image {}x{}", dimensions.0, dimensions.1),
-                    content_type: "Image".to_string(),
-                    plain_text: None,
-                    html_content: None,
-                    rtf_content: None,
-                    image_data: Some(base64_data),
-                    image_format: Some(format),
-                    image_dimensions: Some(dimensions),
-                    file_paths: None,
-                    file_list: None,
-                    custom_formats: None,
-                };
-
-                let storage = get_clipboard_history_storage();
-                if let Ok(mut history) = storage.lock() {
-                    // Check if this image already exists (avoid duplicates)
-                    if !history.iter().any(|item| item.content_type == "Image" &&
-                                          item.image_dimensions == Some(dimensions)) {
-                        history.push_front(history_item);
-                        
-                        if history.len() > 50 {
-                            history.pop_back();
-                        }
-                    }
-                }
+pub async fn get_clipboard_rules() -> Result<Vec<ClipboardRule>, String> {
+    read_clipboard_data(|data| data.rules.clone())
+}
+
+/// Replace all capture rules; the order of `rules` is the evaluation order
+#[tauri::command]
+pub async fn set_clipboard_rules(mut rules: Vec<ClipboardRule>) -> Result<Vec<ClipboardRule>, String> {
+    validate_rules(&mut rules)?;
+    update_clipboard_data(|data| {
+        data.rules = rules;
+        Ok(data.rules.clone())
+    })
+}
+
+/// Add a capture rule at the end of the list, or replace the rule with the same ID
+#[tauri::command]
+pub async fn save_clipboard_rule(rule: ClipboardRule) -> Result<ClipboardRule, String> {
+    update_clipboard_data(|data| {
+        let mut rules = data.rules.clone();
+        let index = match rules.iter().position(|existing| !rule.id.is_empty() && existing.id == rule.id) {
+            Some(index) => {
+                rules[index] = rule;
+                index
             }
-            Err(e) => {
-                eprintln!("Failed to process image data: {}", e);
+            None => {
+                rules.push(rule);
+                rules.len() - 1
             }
-        }
-    }
-    
-    // Get current text content and add it to history if it's new
-    if let Ok(current_content) = app.clipboard().read_text() {
-        if !current_content.is_empty() {
-            add_to_clipboard_history(app, current_content).await?;
-        }
+        };
+        validate_rules(&mut rules)?;
+
+        let saved = rules[index].clone();
+        data.rules = rules;
+        Ok(saved)
+    })
+}
+
+#[tauri::command]
+pub async fn delete_clipboard_rule(id: String) -> Result<(), String> {
+    update_clipboard_data(|data| {
+        let index = data.rules.iter()
+            .position(|rule| rule.id == id)
+            .ok_or_else(|| format!("Clipboard rule '{}' not found", id))?;
+        data.rules.remove(index);
+        Ok(())
+    })
+}
+
+/// Dry run: explain which rule would handle a copy of `content`
+/// Evaluates the saved rules unless draft `rules` are given; the content type is detected when omitted
+#[tauri::command]
+pub async fn test_clipboard_rules(
+    content: String,
+    content_type: Option<String>,
+    source_app: Option<String>,
+    rules: Option<Vec<ClipboardRule>>,
+) -> Result<ClipboardRuleEvaluation, String> {
+    let content_type = content_type.unwrap_or_else(|| detect_content_type(&content));
+    let source_app = source_app.map(|name| ClipboardSourceApp {
+        name: Some(name),
+        instance: None,
+        pid: None,
+        executable: None,
+        window_id: None,
+        platform: "sample".to_string(),
+    });
+    let sample = RuleSample {
+        content: &content,
+        content_type: &content_type,
+        size: content.len(),
+        source_app: source_app.as_ref(),
+    };
+
+    match rules {
+        Some(rules) => Ok(evaluate_rules(&rules, &sample)),
+        None => read_clipboard_data(|data| evaluate_rules(&data.rules, &sample)),
     }
-    Ok(())
 }
 
+/// Evaluate text as an arithmetic expression or unit conversion, e.g. `(1200*1.19)/12` or `72 in to cm`
 #[tauri::command]
-pub async fn clear_clipboard_history(_app: tauri::AppHandle) -> Result<(), String> {
-    let storage = get_clipboard_history_storage();
-    if let Ok(mut history) = storage.lock() {
-        history.clear();
+pub async fn evaluate_clipboard_calculation(expression: String) -> Result<Option<CalculationResult>, String> {
+    Ok(evaluate_calculation(&expression))
+}
+
+/// Copy the calculated result of a history item to the clipboard
+#[tauri::command]
+pub async fn copy_calculation_result(
+    worker: State<'_, ClipboardWorker>,
+    item_id: String,
+) -> Result<CalculationResult, String> {
+    let calculation = get_history_item(&item_id)?
+        .calculation
+        .ok_or_else(|| format!("Clipboard history item '{}' is not a calculation", item_id))?;
+
+    let display = calculation.display.clone();
+    worker.run(move |backend| backend.write_text(&display)).await?;
+    Ok(calculation)
+}
+
+/// Text of a history item that can be diffed
+fn history_item_text(id: &str) -> Result<String, String> {
+    let item = get_history_item(id)?;
+    if item.content_type == "Image" {
+        return Err(format!("Clipboard history item '{}' is an image and cannot be diffed", id));
     }
-    Ok(())
+    if item.hide_preview {
+        return Err(format!("Clipboard history item '{}' is stored without a preview and cannot be diffed", id));
+    }
+    Ok(item.plain_text.unwrap_or(item.primary_content))
 }
 
+/// Diff two history items, or a history item against the current clipboard when `new_id` is omitted
+/// `formats` selects the optional renderings; `copy_as` copies one of them to the clipboard
 #[tauri::command]
-pub async fn clear_clipboard(app: tauri::AppHandle) -> Result<(), String> {
-    app.clipboard()
-        .write_text("")
-        .map_err(|e| format!("Failed to clear clipboard: {}", e))
-}
\ No newline at end of file
+pub async fn diff_clipboard_items(
+    worker: State<'_, ClipboardWorker>,
+    old_id: String,
+    new_id: Option<String>,
+    formats: Option<Vec<DiffOutputFormat>>,
+    context_lines: Option<usize>,
+    copy_as: Option<DiffOutputFormat>,
+) -> Result<ClipboardDiff, String> {
+    let old = history_item_text(&old_id)?;
+    let new = match &new_id {
+        Some(id) => history_item_text(id)?,
+        None => worker.read_text().await?,
+    };
+    let old_label = old_id.clone();
+    let new_label = new_id.clone().unwrap_or_else(|| "clipboard".to_string());
+
+    let formats = formats.unwrap_or_default();
+
+    // Diffing large texts is CPU-bound, so keep it off the async runtime
+    let (diff, copy_text) = tauri::async_runtime::spawn_blocking(move || {
+        let wants = |format: DiffOutputFormat| formats.contains(&format) || copy_as == Some(format);
+
+        let (lines, similarity) = diff_lines(&old, &new);
+        // Copying as HTML offers the unified diff to text-only targets
+        let unified = (wants(DiffOutputFormat::Unified) || copy_as == Some(DiffOutputFormat::Html))
+            .then(|| unified_diff(&old, &new, &old_label, &new_label, context_lines.unwrap_or(3)));
+        let html = wants(DiffOutputFormat::Html)
+            .then(|| side_by_side_html(&old, &new, &old_label, &new_label));
+
+        let copy_text = match copy_as {
+            Some(DiffOutputFormat::Unified) => unified.clone().map(|text| (text, None)),
+            Some(DiffOutputFormat::Html) => html.clone().map(|markup| (markup, unified.clone())),
+            None => None,
+        };
+
+        let diff = ClipboardDiff {
+            old_id,
+            new_id,
+            insertions: lines.iter().filter(|line| line.kind == DiffChangeKind::Insert).count(),
+            deletions: lines.iter().filter(|line| line.kind == DiffChangeKind::Delete).count(),
+            lines,
+            words: diff_words(&old, &new),
+            similarity,
+            unified: unified.filter(|_| wants(DiffOutputFormat::Unified)),
+            html,
+        };
+        (diff, copy_text)
+    })
+    .await
+    .map_err(|e| format!("Failed to diff clipboard items: {}", e))?;
+
+    match (copy_as, copy_text) {
+        (Some(DiffOutputFormat::Html), Some((html, alt_text))) => {
+            worker.run(move |backend| backend.write_html(&html, alt_text.as_deref())).await?;
+        }
+        (Some(_), Some((text, _))) => {
+            worker.run(move |backend| backend.write_text(&text)).await?;
+        }
+        _ => {}
+    }
+
+    Ok(diff)
+}
@@ -0,0 +1,114 @@
+//! Text diffs between clipboard contents
+//!
+//! Produces a structured line and word diff plus optional unified diff text
+//! and a side-by-side HTML table.
+
+use crate::features::clipboard::types::{DiffChangeKind, DiffLine, DiffSegment};
+use similar::{ChangeTag, TextDiff};
+
+fn change_kind(tag: ChangeTag) -> DiffChangeKind {
+    match tag {
+        ChangeTag::Equal => DiffChangeKind::Equal,
+        ChangeTag::Insert => DiffChangeKind::Insert,
+        ChangeTag::Delete => DiffChangeKind::Delete,
+    }
+}
+
+fn strip_line_break(line: &str) -> &str {
+    line.strip_suffix('\n').map(|line| line.strip_suffix('\r').unwrap_or(line)).unwrap_or(line)
+}
+
+/// Line-level diff with line numbers on both sides
+pub fn diff_lines(old: &str, new: &str) -> (Vec<DiffLine>, f32) {
+    let diff = TextDiff::from_lines(old, new);
+    let lines = diff.iter_all_changes()
+        .map(|change| DiffLine {
+            kind: change_kind(change.tag()),
+            old_line: change.old_index().map(|index| index + 1),
+            new_line: change.new_index().map(|index| index + 1),
+            text: strip_line_break(change.value()).to_string(),
+        })
+        .collect();
+    (lines, diff.ratio())
+}
+
+/// Word-level diff over the whole text, merging neighbouring words of the same kind
+pub fn diff_words(old: &str, new: &str) -> Vec<DiffSegment> {
+    let diff = TextDiff::from_words(old, new);
+    let mut segments: Vec<DiffSegment> = Vec::new();
+    for change in diff.iter_all_changes() {
+        let kind = change_kind(change.tag());
+        match segments.last_mut() {
+            Some(last) if last.kind == kind => last.text.push_str(change.value()),
+            _ => segments.push(DiffSegment { kind, text: change.value().to_string() }),
+        }
+    }
+    segments
+}
+
+/// Unified diff text with `context_lines` lines of context around each change
+pub fn unified_diff(old: &str, new: &str, old_label: &str, new_label: &str, context_lines: usize) -> String {
+    let diff = TextDiff::from_lines(old, new);
+    let mut unified = diff.unified_diff();
+    unified.context_radius(context_lines).header(old_label, new_label);
+    unified.to_string()
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn html_cell(line: Option<(usize, &str)>, class: &str) -> String {
+    match line {
+        Some((number, text)) => format!(
+            "<td class=\"diff-line-number\">{}</td><td class=\"{}\">{}</td>",
+            number,
+            class,
+            escape_html(strip_line_break(text))
+        ),
+        None => "<td class=\"diff-line-number\"></td><td class=\"diff-empty\"></td>".to_string(),
+    }
+}
+
+/// Side-by-side HTML table, old text on the left and new text on the right
+/// Replaced lines are paired up row by row
+pub fn side_by_side_html(old: &str, new: &str, old_label: &str, new_label: &str) -> String {
+    let diff = TextDiff::from_lines(old, new);
+    let (old_lines, new_lines) = (diff.old_slices(), diff.new_slices());
+
+    let mut html = String::from(concat!(
+        "<table class=\"clipboard-diff\" style=\"border-collapse:collapse;font-family:monospace;white-space:pre-wrap\">",
+        "<style>.diff-delete{background:#fdd}.diff-insert{background:#dfd}.diff-empty{background:#eee}",
+        ".diff-line-number{color:#888;text-align:right;padding-right:8px}</style>",
+    ));
+    html.push_str(&format!(
+        "<thead><tr><th colspan=\"2\">{}</th><th colspan=\"2\">{}</th></tr></thead><tbody>",
+        escape_html(old_label),
+        escape_html(new_label)
+    ));
+
+    for op in diff.ops() {
+        let (tag, old_range, new_range) = op.as_tag_tuple();
+        let rows = old_range.len().max(new_range.len());
+        for row in 0..rows {
+            let old_index = old_range.start + row;
+            let new_index = new_range.start + row;
+            let left = (row < old_range.len()).then(|| (old_index + 1, old_lines[old_index]));
+            let right = (row < new_range.len()).then(|| (new_index + 1, new_lines[new_index]));
+            let (left_class, right_class) = match tag {
+                similar::DiffTag::Equal => ("diff-equal", "diff-equal"),
+                _ => ("diff-delete", "diff-insert"),
+            };
+            html.push_str("<tr>");
+            html.push_str(&html_cell(left, left_class));
+            html.push_str(&html_cell(right, right_class));
+            html.push_str("</tr>");
+        }
+    }
+
+    html.push_str("</tbody></table>");
+    html
+}
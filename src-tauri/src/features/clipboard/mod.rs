@@ -2,15 +2,29 @@
 //! 
 //! This module provides advanced clipboard functionality including:
 //! - Multi-format clipboard support (text, HTML, RTF, images, files)
-//! - Clipboard history tracking
+//! - Clipboard history tracking with source application attribution
+//! - Named collections persisted across sessions
+//! - Collect mode gathering successive copies into a paste stack
+//! - Capture rules to ignore, mask, expire or file copies
+//! - Inline calculator and unit conversion for copied expressions
+//! - Line and word diffs between clipboard contents
 //! - Format detection and analysis
 //! - Cross-platform compatibility
+//! - Pluggable clipboard backends (system clipboard or in-memory)
 
+pub mod backend;
+pub mod calculator;
 pub mod commands;
+pub mod diff;
+pub mod rules;
+pub mod source_app;
+pub mod store;
 pub mod types;
 pub mod utils;
+pub mod worker;
 
 // Re-export all commands for easy access
+#[allow(unused_imports)] // The binary imports the command modules directly
 pub use commands::*;
 // Re-export types and utils when needed
 // pub use types::*;
@@ -0,0 +1,258 @@
+//! Capture rules
+//!
+//! Rules decide what happens to a copy before it is inserted into the history.
+//! They match on source application, a regex over the content, content type
+//! and size, are evaluated in order (first match wins) and are persisted with
+//! the rest of the clipboard data.
+
+use crate::features::clipboard::source_app::source_app_matches;
+use crate::features::clipboard::store::{new_clipboard_collection, read_clipboard_data, update_clipboard_data};
+use crate::features::clipboard::types::{
+    ClipboardHistoryItem, ClipboardRule, ClipboardRuleAction, ClipboardRuleCheck, ClipboardRuleConditions,
+    ClipboardRuleEvaluation, ClipboardSourceApp,
+};
+use crate::features::clipboard::utils::{generate_clipboard_id, history_item_size};
+use regex::{Regex, RegexBuilder};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+/// Upper bound for the compiled size of a rule pattern
+const MAX_PATTERN_SIZE: usize = 1 << 20;
+
+// Rules are evaluated on every capture, so compiled patterns are kept around
+static COMPILED_PATTERNS: OnceLock<Mutex<HashMap<String, Regex>>> = OnceLock::new();
+
+fn compiled_pattern(pattern: &str) -> Result<Regex, String> {
+    let cache = COMPILED_PATTERNS.get_or_init(|| Mutex::new(HashMap::new()));
+    let mut cache = cache.lock().map_err(|e| e.to_string())?;
+    if let Some(regex) = cache.get(pattern) {
+        return Ok(regex.clone());
+    }
+
+    let regex = RegexBuilder::new(pattern)
+        .size_limit(MAX_PATTERN_SIZE)
+        .build()
+        .map_err(|e| format!("Invalid content pattern '{}': {}", pattern, e))?;
+    cache.insert(pattern.to_string(), regex.clone());
+    Ok(regex)
+}
+
+/// What a rule is matched against
+pub struct RuleSample<'a> {
+    pub content: &'a str,
+    pub content_type: &'a str,
+    pub size: usize,
+    pub source_app: Option<&'a ClipboardSourceApp>,
+}
+
+/// Check every condition of a rule, explaining each outcome
+fn check_conditions(conditions: &ClipboardRuleConditions, sample: &RuleSample) -> (bool, Vec<String>) {
+    let mut matched = true;
+    let mut reasons = Vec::new();
+
+    if let Some(query) = &conditions.source_app {
+        let ok = sample.source_app.is_some_and(|app| source_app_matches(app, query));
+        let actual = sample.source_app
+            .and_then(|app| app.name.clone().or_else(|| app.executable.clone()))
+            .unwrap_or_else(|| "unknown".to_string());
+        reasons.push(if ok {
+            format!("Source app '{}' matches '{}'", actual, query)
+        } else {
+            format!("Source app '{}' does not match '{}'", actual, query)
+        });
+        matched &= ok;
+    }
+
+    if let Some(pattern) = &conditions.content_pattern {
+        match compiled_pattern(pattern) {
+            Ok(regex) => {
+                let ok = regex.is_match(sample.content);
+                reasons.push(if ok {
+                    format!("Content matches /{}/", pattern)
+                } else {
+                    format!("Content does not match /{}/", pattern)
+                });
+                matched &= ok;
+            }
+            Err(e) => {
+                reasons.push(e);
+                matched = false;
+            }
+        }
+    }
+
+    if let Some(content_type) = &conditions.content_type {
+        let ok = sample.content_type.eq_ignore_ascii_case(content_type);
+        reasons.push(if ok {
+            format!("Content type is {}", content_type)
+        } else {
+            format!("Content type {} is not {}", sample.content_type, content_type)
+        });
+        matched &= ok;
+    }
+
+    if let Some(min_size) = conditions.min_size {
+        let ok = sample.size >= min_size;
+        reasons.push(format!("Size {} bytes is {} the minimum of {} bytes",
+            sample.size, if ok { "at least" } else { "below" }, min_size));
+        matched &= ok;
+    }
+
+    if let Some(max_size) = conditions.max_size {
+        let ok = sample.size <= max_size;
+        reasons.push(format!("Size {} bytes is {} the maximum of {} bytes",
+            sample.size, if ok { "within" } else { "above" }, max_size));
+        matched &= ok;
+    }
+
+    if reasons.is_empty() {
+        reasons.push("Rule has no conditions and matches everything".to_string());
+    }
+
+    (matched, reasons)
+}
+
+/// Evaluate rules in order; the first enabled rule whose conditions all match wins
+pub fn evaluate_rules(rules: &[ClipboardRule], sample: &RuleSample) -> ClipboardRuleEvaluation {
+    let mut checks = Vec::new();
+
+    for rule in rules {
+        let (matched, reasons) = if rule.enabled {
+            check_conditions(&rule.conditions, sample)
+        } else {
+            (false, vec!["Rule is disabled".to_string()])
+        };
+
+        checks.push(ClipboardRuleCheck {
+            rule_id: rule.id.clone(),
+            rule_name: rule.name.clone(),
+            matched,
+            reasons,
+        });
+
+        if matched {
+            return ClipboardRuleEvaluation {
+                matched_rule: Some(rule.clone()),
+                action: Some(rule.action.clone()),
+                checks,
+            };
+        }
+    }
+
+    ClipboardRuleEvaluation {
+        matched_rule: None,
+        action: None,
+        checks,
+    }
+}
+
+/// Check rules before they are saved and assign IDs to new ones
+pub fn validate_rules(rules: &mut [ClipboardRule]) -> Result<(), String> {
+    let timestamp = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC").to_string();
+
+    for (index, rule) in rules.iter_mut().enumerate() {
+        rule.name = rule.name.trim().to_string();
+        if rule.name.is_empty() {
+            return Err("Rule name cannot be empty".to_string());
+        }
+        if let Some(pattern) = &rule.conditions.content_pattern {
+            compiled_pattern(pattern)?;
+        }
+        if let (Some(min_size), Some(max_size)) = (rule.conditions.min_size, rule.conditions.max_size)
+            && min_size > max_size
+        {
+            return Err(format!("Rule '{}' has a minimum size above its maximum size", rule.name));
+        }
+        match &rule.action {
+            ClipboardRuleAction::StoreWithExpiry { ttl_seconds: 0 } => {
+                return Err(format!("Rule '{}' must keep items for at least one second", rule.name));
+            }
+            ClipboardRuleAction::StoreInCollection { collection } if collection.trim().is_empty() => {
+                return Err(format!("Rule '{}' needs a collection name", rule.name));
+            }
+            _ => {}
+        }
+        if rule.id.is_empty() {
+            rule.id = generate_clipboard_id(&format!("{}#{}", rule.name, index), &timestamp)
+                .replacen("clip_", "rule_", 1);
+        }
+    }
+
+    for (index, rule) in rules.iter().enumerate() {
+        if rules[..index].iter().any(|other| other.id == rule.id) {
+            return Err(format!("Duplicate rule ID '{}'", rule.id));
+        }
+    }
+
+    Ok(())
+}
+
+/// Evaluate the saved rules against a history item about to be inserted
+fn capture_rule_action(item: &ClipboardHistoryItem) -> Option<ClipboardRuleAction> {
+    let content = item.plain_text.as_deref().unwrap_or(&item.primary_content);
+    let sample = RuleSample {
+        content,
+        content_type: &item.content_type,
+        size: history_item_size(item),
+        source_app: item.source_app.as_ref(),
+    };
+
+    read_clipboard_data(|data| evaluate_rules(&data.rules, &sample).action)
+        .ok()
+        .flatten()
+}
+
+/// Apply the capture rules to a new history item
+/// Returns None if a rule says the copy must not be recorded
+pub fn apply_capture_rules(mut item: ClipboardHistoryItem) -> Option<ClipboardHistoryItem> {
+    match capture_rule_action(&item) {
+        Some(ClipboardRuleAction::Ignore) => return None,
+        Some(ClipboardRuleAction::StoreWithoutPreview) => {
+            // Formats carry content previews and raw data
+            item.formats.clear();
+            item.hide_preview = true;
+        }
+        Some(ClipboardRuleAction::StoreWithExpiry { ttl_seconds }) => {
+            let ttl = i64::try_from(ttl_seconds).unwrap_or(i64::MAX);
+            item.expires_at = Some(chrono::Utc::now().timestamp().saturating_add(ttl));
+        }
+        Some(ClipboardRuleAction::StoreInCollection { collection }) => {
+            if let Err(e) = add_to_named_collection(&collection, &item) {
+                eprintln!("Failed to store clipboard item in collection '{}': {}", collection, e);
+            }
+        }
+        None => {}
+    }
+    Some(item)
+}
+
+/// Append an item to the collection with the given name, creating the collection if needed
+/// Items whose content is already in the collection are skipped without touching the store
+fn add_to_named_collection(name: &str, item: &ClipboardHistoryItem) -> Result<(), String> {
+    let name = name.trim();
+    let stored = read_clipboard_data(|data| {
+        data.collections.iter().any(|collection| {
+            collection.name.eq_ignore_ascii_case(name)
+                && collection.items.iter().any(|existing| existing.primary_content == item.primary_content)
+        })
+    })?;
+    if stored {
+        return Ok(());
+    }
+
+    update_clipboard_data(|data| {
+        let index = match data.collections.iter().position(|collection| collection.name.eq_ignore_ascii_case(name)) {
+            Some(index) => index,
+            None => {
+                data.collections.push(new_clipboard_collection(name.to_string()));
+                data.collections.len() - 1
+            }
+        };
+
+        let collection = &mut data.collections[index];
+        if !collection.items.iter().any(|existing| existing.primary_content == item.primary_content) {
+            collection.items.push(item.clone());
+        }
+        Ok(())
+    })
+}
@@ -0,0 +1,238 @@
+//! Source application attribution
+//!
+//! On X11 the application that copied something owns the `CLIPBOARD` selection.
+//! The owner window (or its client leader) usually carries `_NET_WM_PID` and
+//! `WM_CLASS`, which identify the application. Wayland has no protocol that
+//! exposes the clipboard owner, so there only copies from X clients running
+//! under XWayland can be attributed. Run under Xvfb to exercise the X11 path
+//! without a desktop session.
+
+use crate::features::clipboard::types::ClipboardSourceApp;
+
+/// Identify the application that currently owns the clipboard, if possible
+pub fn detect_source_app() -> Option<ClipboardSourceApp> {
+    #[cfg(target_os = "linux")]
+    {
+        x11::clipboard_owner_app()
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        None
+    }
+}
+
+/// Whether a source app matches a filter by WM_CLASS, instance or process name (case-insensitive)
+pub fn source_app_matches(app: &ClipboardSourceApp, query: &str) -> bool {
+    [&app.name, &app.instance, &app.executable]
+        .into_iter()
+        .flatten()
+        .any(|value| value.eq_ignore_ascii_case(query))
+}
+
+#[cfg(target_os = "linux")]
+mod x11 {
+    use crate::features::clipboard::types::ClipboardSourceApp;
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use x11rb::protocol::xproto::{Atom, AtomEnum, ConnectionExt, Window};
+    use x11rb::rust_connection::RustConnection;
+
+    /// How far up the window tree to look for application properties
+    const MAX_PARENT_DEPTH: usize = 4;
+
+    /// Instance and class name from WM_CLASS
+    type WmClass = (Option<String>, Option<String>);
+
+    struct X11Session {
+        conn: RustConnection,
+        clipboard: Atom,
+        net_wm_pid: Atom,
+        wm_client_leader: Atom,
+    }
+
+    // Captures run on the clipboard worker thread, so one connection is reused between polls
+    static X11_SESSION: Mutex<Option<X11Session>> = Mutex::new(None);
+
+    // Set once connecting failed, so polling does not retry and log on every capture
+    static CONNECT_FAILED: AtomicBool = AtomicBool::new(false);
+
+    fn x11_error(e: impl std::fmt::Display) -> String {
+        format!("X11 request failed: {}", e)
+    }
+
+    pub fn clipboard_owner_app() -> Option<ClipboardSourceApp> {
+        std::env::var_os("DISPLAY")?;
+
+        let mut session = X11_SESSION.lock().ok()?;
+        if session.is_none() {
+            if CONNECT_FAILED.load(Ordering::Relaxed) {
+                return None;
+            }
+            match X11Session::connect() {
+                Ok(connected) => *session = Some(connected),
+                Err(e) => {
+                    eprintln!("Failed to connect to X server, source app detection is disabled: {}", e);
+                    CONNECT_FAILED.store(true, Ordering::Relaxed);
+                    return None;
+                }
+            }
+        }
+
+        match session.as_ref()?.clipboard_owner_app() {
+            Ok(app) => app,
+            Err(e) => {
+                // The connection may be broken; reconnect on the next capture
+                eprintln!("Failed to detect clipboard source app: {}", e);
+                *session = None;
+                None
+            }
+        }
+    }
+
+    impl X11Session {
+        fn connect() -> Result<Self, String> {
+            let (conn, _) = x11rb::connect(None).map_err(x11_error)?;
+            let intern = |name: &[u8]| -> Result<Atom, String> {
+                Ok(conn.intern_atom(false, name).map_err(x11_error)?.reply().map_err(x11_error)?.atom)
+            };
+            let clipboard = intern(b"CLIPBOARD")?;
+            let net_wm_pid = intern(b"_NET_WM_PID")?;
+            let wm_client_leader = intern(b"WM_CLIENT_LEADER")?;
+
+            Ok(Self { conn, clipboard, net_wm_pid, wm_client_leader })
+        }
+
+        fn clipboard_owner_app(&self) -> Result<Option<ClipboardSourceApp>, String> {
+            let owner = self.conn.get_selection_owner(self.clipboard)
+                .map_err(x11_error)?
+                .reply()
+                .map_err(x11_error)?
+                .owner;
+            if owner == x11rb::NONE {
+                return Ok(None);
+            }
+
+            // Toolkits often own selections through an unmapped helper window,
+            // so also look at its client leader and ancestors
+            let mut candidates = vec![owner];
+            if let Some(leader) = self.property32(owner, self.wm_client_leader, AtomEnum::WINDOW.into())? {
+                candidates.push(leader);
+            }
+            let mut window = owner;
+            for _ in 0..MAX_PARENT_DEPTH {
+                let tree = self.conn.query_tree(window).map_err(x11_error)?.reply().map_err(x11_error)?;
+                if tree.parent == x11rb::NONE || tree.parent == tree.root {
+                    break;
+                }
+                candidates.push(tree.parent);
+                window = tree.parent;
+            }
+
+            let mut pid = None;
+            let mut class = None;
+            for window in candidates {
+                if pid.is_none() {
+                    pid = self.property32(window, self.net_wm_pid, AtomEnum::CARDINAL.into())?;
+                }
+                if class.is_none() {
+                    class = self.wm_class(window)?;
+                }
+                if pid.is_some() && class.is_some() {
+                    break;
+                }
+            }
+
+            // Nothing identifies the owner, e.g. the XWayland bridge for a native Wayland client
+            if pid.is_none() && class.is_none() {
+                return Ok(None);
+            }
+
+            let executable = pid.and_then(process_name);
+            let (instance, name) = class.unwrap_or_default();
+            let platform = if std::env::var_os("WAYLAND_DISPLAY").is_some() { "xwayland" } else { "x11" };
+            Ok(Some(ClipboardSourceApp {
+                name: name.or_else(|| executable.clone()),
+                instance,
+                pid,
+                executable,
+                window_id: Some(owner),
+                platform: platform.to_string(),
+            }))
+        }
+
+        fn property32(&self, window: Window, property: Atom, type_: Atom) -> Result<Option<u32>, String> {
+            let reply = self.conn.get_property(false, window, property, type_, 0, 1)
+                .map_err(x11_error)?
+                .reply()
+                .map_err(x11_error)?;
+            Ok(reply.value32().and_then(|mut values| values.next()))
+        }
+
+        /// WM_CLASS holds the instance and class names, each NUL terminated
+        fn wm_class(&self, window: Window) -> Result<Option<WmClass>, String> {
+            let reply = self.conn.get_property(false, window, AtomEnum::WM_CLASS, AtomEnum::STRING, 0, 256)
+                .map_err(x11_error)?
+                .reply()
+                .map_err(x11_error)?;
+            if reply.value.is_empty() {
+                return Ok(None);
+            }
+
+            let mut parts = reply.value
+                .split(|byte| *byte == 0)
+                .map(|part| String::from_utf8_lossy(part).into_owned())
+                .map(|part| Some(part).filter(|part| !part.is_empty()));
+            let instance = parts.next().flatten();
+            let class = parts.next().flatten();
+            Ok(Some((instance, class)))
+        }
+    }
+
+    /// Process name of a PID as reported by procfs
+    fn process_name(pid: u32) -> Option<String> {
+        std::fs::read_to_string(format!("/proc/{}/comm", pid))
+            .ok()
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::{AtomEnum, ConnectionExt, CreateWindowAux, PropMode, WindowClass};
+    use x11rb::wrapper::ConnectionExt as _;
+
+    /// Needs an X server; run with `xvfb-run cargo test`, skipped without DISPLAY
+    #[test]
+    fn detects_selection_owner_from_window_properties() {
+        if std::env::var_os("DISPLAY").is_none() {
+            return;
+        }
+        let (conn, screen_num) = x11rb::connect(None).expect("connect to X server");
+        let root = conn.setup().roots[screen_num].root;
+        let window = conn.generate_id().unwrap();
+        conn.create_window(0, window, root, 0, 0, 1, 1, 0, WindowClass::INPUT_OUTPUT, 0, &CreateWindowAux::new())
+            .unwrap();
+
+        let net_wm_pid = conn.intern_atom(false, b"_NET_WM_PID").unwrap().reply().unwrap().atom;
+        let clipboard = conn.intern_atom(false, b"CLIPBOARD").unwrap().reply().unwrap().atom;
+        conn.change_property8(PropMode::REPLACE, window, AtomEnum::WM_CLASS, AtomEnum::STRING, b"copy-test\0CopyTest\0")
+            .unwrap();
+        conn.change_property32(PropMode::REPLACE, window, net_wm_pid, AtomEnum::CARDINAL, &[std::process::id()])
+            .unwrap();
+        conn.set_selection_owner(window, clipboard, x11rb::CURRENT_TIME).unwrap();
+        // Wait until the server applied the requests
+        conn.get_selection_owner(clipboard).unwrap().reply().unwrap();
+
+        let app = detect_source_app().expect("clipboard owner detected");
+        assert_eq!(app.name.as_deref(), Some("CopyTest"));
+        assert_eq!(app.instance.as_deref(), Some("copy-test"));
+        assert_eq!(app.pid, Some(std::process::id()));
+        assert_eq!(app.window_id, Some(window));
+        assert!(app.executable.is_some());
+        assert!(source_app_matches(&app, "copytest"));
+    }
+}
@@ -0,0 +1,111 @@
+//! Persistent clipboard data
+//!
+//! Clipboard data that outlives the session (collections and capture rules) is kept
+//! in `clipboard.json` in the app data directory. Every mutation is applied to
+//! a copy and only committed once the file was written, so memory and disk
+//! never disagree.
+
+use crate::features::clipboard::types::{ClipboardCollection, ClipboardRule};
+use crate::features::clipboard::utils::generate_clipboard_id;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use tauri::Manager;
+
+const CLIPBOARD_DATA_FILE: &str = "clipboard.json";
+
+/// Everything persisted by the clipboard feature
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct ClipboardData {
+    #[serde(default)]
+    pub collections: Vec<ClipboardCollection>, // In user-defined order
+    #[serde(default)]
+    pub rules: Vec<ClipboardRule>, // Capture rules in evaluation order
+}
+
+/// Create an empty collection with a fresh ID
+pub fn new_clipboard_collection(name: String) -> ClipboardCollection {
+    let created_at = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC").to_string();
+    ClipboardCollection {
+        id: generate_clipboard_id(&name, &created_at).replacen("clip_", "collection_", 1),
+        name,
+        created_at,
+        items: Vec::new(),
+    }
+}
+
+struct ClipboardStore {
+    path: Option<PathBuf>, // None keeps the data in memory only
+    data: Mutex<ClipboardData>,
+}
+
+static CLIPBOARD_STORE: OnceLock<ClipboardStore> = OnceLock::new();
+
+/// Load the persisted clipboard data from the app data directory
+/// Call once during setup; without it the data is kept in memory only
+pub fn init_clipboard_store(app: &tauri::AppHandle) {
+    let path = match app.path().app_data_dir() {
+        Ok(dir) => Some(dir.join(CLIPBOARD_DATA_FILE)),
+        Err(e) => {
+            eprintln!("Failed to resolve app data directory, clipboard data will not be saved: {}", e);
+            None
+        }
+    };
+
+    let data = path.as_ref().map(|path| load_clipboard_data(path)).unwrap_or_default();
+    if CLIPBOARD_STORE.set(ClipboardStore { path, data: Mutex::new(data) }).is_err() {
+        eprintln!("Clipboard store was already initialized");
+    }
+}
+
+fn load_clipboard_data(path: &Path) -> ClipboardData {
+    match std::fs::read_to_string(path) {
+        Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+            eprintln!("Failed to parse {}, starting with empty clipboard data: {}", path.display(), e);
+            ClipboardData::default()
+        }),
+        // Nothing saved yet
+        Err(_) => ClipboardData::default(),
+    }
+}
+
+fn save_clipboard_data(path: &Path, data: &ClipboardData) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create clipboard data directory: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(data)
+        .map_err(|e| format!("Failed to serialize clipboard data: {}", e))?;
+
+    // Write next to the target and rename so a crash never leaves a truncated file
+    let tmp_path = path.with_extension("json.tmp");
+    std::fs::write(&tmp_path, json)
+        .map_err(|e| format!("Failed to save clipboard data: {}", e))?;
+    std::fs::rename(&tmp_path, path)
+        .map_err(|e| format!("Failed to save clipboard data: {}", e))
+}
+
+fn clipboard_store() -> &'static ClipboardStore {
+    CLIPBOARD_STORE.get_or_init(|| ClipboardStore { path: None, data: Mutex::new(ClipboardData::default()) })
+}
+
+/// Read the clipboard data
+pub fn read_clipboard_data<T>(read: impl FnOnce(&ClipboardData) -> T) -> Result<T, String> {
+    let data = clipboard_store().data.lock().map_err(|e| e.to_string())?;
+    Ok(read(&data))
+}
+
+/// Modify the clipboard data and persist it
+/// Nothing changes if `update` fails or the data cannot be saved
+pub fn update_clipboard_data<T>(update: impl FnOnce(&mut ClipboardData) -> Result<T, String>) -> Result<T, String> {
+    let store = clipboard_store();
+    let mut data = store.data.lock().map_err(|e| e.to_string())?;
+
+    let mut updated = data.clone();
+    let result = update(&mut updated)?;
+    if let Some(path) = &store.path {
+        save_clipboard_data(path, &updated)?;
+    }
+    *data = updated;
+    Ok(result)
+}
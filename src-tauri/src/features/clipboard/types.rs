@@ -25,9 +25,238 @@ pub struct ClipboardHistoryItem {
     pub image_data: Option<String>, // Base64 encoded image data
     pub image_format: Option<String>, // Image format (png, jpg, etc.)
     pub image_dimensions: Option<(u32, u32)>, // Width, Height
+    #[serde(default)]
+    pub image_hash: Option<String>, // Hash of the raw pixels, used to spot re-copied images
     // File formats
     pub file_paths: Option<Vec<String>>, // File paths for file drops
     pub file_list: Option<String>, // File list as text
     // Advanced formats
     pub custom_formats: Option<std::collections::HashMap<String, String>>, // Custom format data
-}
\ No newline at end of file
+    // User annotations
+    #[serde(default)]
+    pub label: Option<String>, // User-given name for the item
+    #[serde(default)]
+    pub note: Option<String>, // Free-form note attached to the item
+    #[serde(default)]
+    pub pinned: bool, // Pinned items are never evicted from history
+    #[serde(default)]
+    pub source_app: Option<ClipboardSourceApp>, // Application that owned the clipboard at capture time
+    #[serde(default)]
+    pub hide_preview: bool, // Set by capture rules; the frontend should mask the content
+    #[serde(default)]
+    pub expires_at: Option<i64>, // Unix timestamp in seconds after which the item is dropped from history
+    #[serde(default)]
+    pub calculation: Option<CalculationResult>, // Result when the content is an arithmetic or unit expression
+}
+/// How an image is fitted into the requested dimensions when resizing
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageFitMode {
+    /// Scale to fit inside the box, preserving aspect ratio
+    Contain,
+    /// Scale to fill the box, preserving aspect ratio and cropping the overflow
+    Cover,
+    /// Stretch to exactly the requested dimensions
+    Fill,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ImageOperationResult {
+    pub image_data: String, // Base64 encoded image data
+    pub image_format: String, // Encoding of image_data (png, jpeg, etc.)
+    pub image_dimensions: (u32, u32), // Width, Height
+    pub data_size: usize, // Size of the encoded image in bytes
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DecodedBarcode {
+    pub format: String, // Barcode symbology (QR_CODE, EAN_13, CODE_128, etc.)
+    pub text: String,
+}
+
+/// Payload of the `clipboard-history-updated` event
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ClipboardHistoryDelta {
+    pub inserted: Vec<String>, // IDs of items added to the history
+    pub removed: Vec<String>, // IDs of items deleted, evicted or cleared
+    pub updated: Vec<String>, // IDs of items whose content, annotations, pin state or position changed
+    pub generation: u64, // History generation after this change
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RecopiedItem {
+    pub id: String,
+    pub content_type: String,
+    pub preview: String, // Label if set, otherwise the start of the content
+    pub copy_count: u64, // First copy plus every deduplicated re-copy
+}
+
+/// Aggregated clipboard history statistics for dashboards
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ClipboardHistoryStats {
+    pub total_items: usize,
+    pub pinned_items: usize,
+    pub counts_by_type: std::collections::HashMap<String, usize>,
+    pub total_size: usize, // Content size of all items in bytes
+    pub average_size: f64,
+    pub most_recopied: Vec<RecopiedItem>, // Most re-copied items still in history, highest count first
+    pub copies_per_hour: Vec<u64>, // 24 buckets by hour of day (UTC)
+    pub total_copies: u64, // Copies seen by the capture path, including duplicates
+    pub duplicate_hits: u64, // Copies dropped because the content was already in history
+    pub dedup_hit_rate: f64, // duplicate_hits / total_copies
+    pub evictions: u64, // Items dropped because the history was full
+}
+
+/// User-named board of clipboard items; collection items are never evicted
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ClipboardCollection {
+    pub id: String,
+    pub name: String,
+    pub created_at: String,
+    pub items: Vec<ClipboardHistoryItem>, // In user-defined order
+}
+
+/// Order in which `pop_paste_stack` takes items off the paste stack
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PasteStackOrder {
+    /// Oldest copy first
+    Fifo,
+    /// Most recent copy first
+    Lifo,
+}
+
+/// Payload of the `paste-stack-updated` event
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PasteStackState {
+    pub active: bool, // Whether collect mode is on
+    pub items: Vec<String>, // Collected text, oldest first
+}
+
+/// Application that owned the clipboard when an item was captured
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ClipboardSourceApp {
+    pub name: Option<String>, // WM_CLASS class name, or the process name if unset
+    pub instance: Option<String>, // WM_CLASS instance name
+    pub pid: Option<u32>,
+    pub executable: Option<String>, // Process name of pid
+    pub window_id: Option<u32>, // Selection owner window
+    pub platform: String, // Display protocol used for detection ("x11", "xwayland")
+}
+
+/// Conditions of a capture rule; every condition that is set must match
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ClipboardRuleConditions {
+    #[serde(default)]
+    pub source_app: Option<String>, // WM_CLASS, instance or process name (case-insensitive)
+    #[serde(default)]
+    pub content_pattern: Option<String>, // Regular expression searched in the text content
+    #[serde(default)]
+    pub content_type: Option<String>, // Detected content type such as "URL" or "Image" (case-insensitive)
+    #[serde(default)]
+    pub min_size: Option<usize>, // Content size in bytes
+    #[serde(default)]
+    pub max_size: Option<usize>,
+}
+
+/// What happens to a capture matched by a rule
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClipboardRuleAction {
+    /// Do not record the copy at all
+    Ignore,
+    /// Record the copy, but drop its format previews and mark it for masked display
+    StoreWithoutPreview,
+    /// Record the copy and remove it from history after the given time
+    StoreWithExpiry { ttl_seconds: u64 },
+    /// Record the copy and also add it to the named collection, creating it if needed
+    StoreInCollection { collection: String },
+}
+
+/// Capture rule; rules are evaluated in order and the first match wins
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ClipboardRule {
+    #[serde(default)]
+    pub id: String, // Generated when empty
+    pub name: String,
+    #[serde(default = "default_rule_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub conditions: ClipboardRuleConditions,
+    pub action: ClipboardRuleAction,
+}
+
+fn default_rule_enabled() -> bool {
+    true
+}
+
+/// How a single rule fared against a sample
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ClipboardRuleCheck {
+    pub rule_id: String,
+    pub rule_name: String,
+    pub matched: bool,
+    pub reasons: Vec<String>, // One line per condition, or why the rule was skipped
+}
+
+/// Result of evaluating the capture rules against a sample
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ClipboardRuleEvaluation {
+    pub matched_rule: Option<ClipboardRule>,
+    pub action: Option<ClipboardRuleAction>, // None means the copy is recorded normally
+    pub checks: Vec<ClipboardRuleCheck>, // Rules in evaluation order, up to the first match
+}
+
+/// Result of evaluating copied text as a calculation or unit conversion
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CalculationResult {
+    pub expression: String,
+    pub value: f64,
+    pub unit: Option<String>, // Target unit of a conversion
+    pub display: String, // Value as it is copied to the clipboard, e.g. "182.88 cm"
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffChangeKind {
+    Equal,
+    Insert,
+    Delete,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DiffLine {
+    pub kind: DiffChangeKind,
+    pub old_line: Option<usize>, // 1-based line number in the old text
+    pub new_line: Option<usize>, // 1-based line number in the new text
+    pub text: String, // Without the line break
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DiffSegment {
+    pub kind: DiffChangeKind,
+    pub text: String, // Consecutive words and whitespace with the same change kind
+}
+
+/// Rendering of a diff that can be returned or copied to the clipboard
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffOutputFormat {
+    /// Unified diff text as produced by `diff -u`
+    Unified,
+    /// Side-by-side HTML table
+    Html,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ClipboardDiff {
+    pub old_id: String,
+    pub new_id: Option<String>, // None when compared against the current clipboard
+    pub lines: Vec<DiffLine>,
+    pub words: Vec<DiffSegment>,
+    pub insertions: usize, // Inserted lines
+    pub deletions: usize, // Deleted lines
+    pub similarity: f32, // 0.0 (nothing in common) to 1.0 (identical)
+    pub unified: Option<String>,
+    pub html: Option<String>,
+}
@@ -1,15 +1,366 @@
-use crate::features::clipboard::types::ClipboardHistoryItem;
-use image::ImageFormat;
+use crate::features::clipboard::backend::{ClipboardBackend, ClipboardImage};
+use crate::features::clipboard::calculator::evaluate_calculation;
+use crate::features::clipboard::rules::apply_capture_rules;
+use crate::features::clipboard::source_app::detect_source_app;
+use crate::features::clipboard::types::{ClipboardFormat, ClipboardHistoryDelta, ClipboardHistoryItem, ClipboardHistoryStats, ClipboardSourceApp, DecodedBarcode, ImageFitMode, PasteStackOrder, PasteStackState, RecopiedItem};
+use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader};
+use image::imageops::FilterType;
 use std::io::Cursor;
 use std::sync::{Arc, Mutex};
-use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use base64::{Engine as _, engine::general_purpose};
 
 // Global clipboard history storage
 static CLIPBOARD_HISTORY: std::sync::OnceLock<Arc<Mutex<VecDeque<ClipboardHistoryItem>>>> = std::sync::OnceLock::new();
 
+// Maximum number of items kept in the clipboard history (pinned items excluded)
+pub const MAX_HISTORY_ITEMS: usize = 50;
+
+// Bumped on every clipboard history mutation so windows can tell whether they are in sync
+static HISTORY_GENERATION: AtomicU64 = AtomicU64::new(0);
+
 pub fn get_clipboard_history_storage() -> &'static Arc<Mutex<VecDeque<ClipboardHistoryItem>>> {
-    CLIPBOARD_HISTORY.get_or_init(|| Arc::new(Mutex::new(VecDeque::with_capacity(50))))
+    CLIPBOARD_HISTORY.get_or_init(|| Arc::new(Mutex::new(VecDeque::with_capacity(MAX_HISTORY_ITEMS))))
+}
+
+/// Current clipboard history generation
+pub fn current_history_generation() -> u64 {
+    HISTORY_GENERATION.load(Ordering::SeqCst)
+}
+
+/// Describe a history mutation and advance the generation
+/// Call this while still holding the history lock so generations follow mutation order
+pub fn history_delta(inserted: Vec<String>, removed: Vec<String>, updated: Vec<String>) -> ClipboardHistoryDelta {
+    ClipboardHistoryDelta {
+        inserted,
+        removed,
+        updated,
+        generation: HISTORY_GENERATION.fetch_add(1, Ordering::SeqCst) + 1,
+    }
+}
+
+/// Add an item to the front of the history, evicting the oldest unpinned items beyond the limit
+/// Returns the IDs of the evicted items
+pub fn push_history_item(history: &mut VecDeque<ClipboardHistoryItem>, item: ClipboardHistoryItem) -> Vec<String> {
+    history.push_front(item);
+
+    let mut evicted = Vec::new();
+    while history.iter().filter(|item| !item.pinned).count() > MAX_HISTORY_ITEMS {
+        match history.iter().rposition(|item| !item.pinned) {
+            Some(index) => {
+                if let Some(item) = history.remove(index) {
+                    evicted.push(item.id);
+                }
+            }
+            None => break,
+        }
+    }
+    record_evictions(&evicted);
+    evicted
+}
+
+/// Whether an item's rule-assigned expiry has passed
+pub fn is_expired(item: &ClipboardHistoryItem, now: i64) -> bool {
+    item.expires_at.is_some_and(|expires_at| expires_at <= now)
+}
+
+/// Remove expired items from the history and return their IDs
+pub fn remove_expired_items(history: &mut VecDeque<ClipboardHistoryItem>) -> Vec<String> {
+    let now = chrono::Utc::now().timestamp();
+    let mut expired = Vec::new();
+    history.retain(|item| {
+        if is_expired(item, now) {
+            expired.push(item.id.clone());
+            false
+        } else {
+            true
+        }
+    });
+    expired
+}
+
+/// Usage counters maintained by the capture path
+struct HistoryCounters {
+    total_copies: u64,
+    duplicate_hits: u64,
+    evictions: u64,
+    recopies: BTreeMap<String, u64>, // Re-copies per history item ID
+    copies_per_hour: [u64; 24],
+    last_change: Option<u64>, // Fingerprint of the clipboard change counted last
+}
+
+static HISTORY_COUNTERS: Mutex<HistoryCounters> = Mutex::new(HistoryCounters {
+    total_copies: 0,
+    duplicate_hits: 0,
+    evictions: 0,
+    recopies: BTreeMap::new(),
+    copies_per_hour: [0; 24],
+    last_change: None,
+});
+
+/// Count a copy seen by the capture path; `duplicate_of` is the existing item it was deduplicated against
+/// `change` fingerprints the clipboard contents, so a copy recorded by a command and seen again
+/// by the monitor, or an image and its text captured together, count once
+fn record_copy(change: u64, duplicate_of: Option<&str>) {
+    use chrono::Timelike;
+
+    if let Ok(mut counters) = HISTORY_COUNTERS.lock() {
+        if counters.last_change.replace(change) == Some(change) {
+            return;
+        }
+        counters.total_copies += 1;
+        counters.copies_per_hour[chrono::Utc::now().hour() as usize] += 1;
+        if let Some(id) = duplicate_of {
+            counters.duplicate_hits += 1;
+            *counters.recopies.entry(id.to_string()).or_insert(0) += 1;
+        }
+    }
+}
+
+fn record_evictions(evicted: &[String]) {
+    if evicted.is_empty() {
+        return;
+    }
+    if let Ok(mut counters) = HISTORY_COUNTERS.lock() {
+        counters.evictions += evicted.len() as u64;
+        for id in evicted {
+            counters.recopies.remove(id);
+        }
+    }
+}
+
+/// Size of the content held by a history item in bytes
+pub fn history_item_size(item: &ClipboardHistoryItem) -> usize {
+    match (&item.image_data, item.image_dimensions) {
+        // Base64 encodes 3 bytes in 4 characters
+        (Some(data), _) => data.len() / 4 * 3,
+        // An image captured but not encoded yet counts its raw RGBA pixels
+        (None, Some((width, height))) => width as usize * height as usize * 4,
+        (None, None) => item.plain_text.as_ref().unwrap_or(&item.primary_content).len(),
+    }
+}
+
+/// Item as handed to the frontend; items stored without a preview keep only their metadata
+pub fn redact_hidden_content(mut item: ClipboardHistoryItem) -> ClipboardHistoryItem {
+    if item.hide_preview {
+        item.primary_content = String::new();
+        item.plain_text = None;
+        item.html_content = None;
+        item.rtf_content = None;
+        item.image_data = None;
+        item.file_paths = None;
+        item.file_list = None;
+        item.custom_formats = None;
+        item.calculation = None;
+    }
+    item
+}
+
+/// Compute history statistics, listing at most `top` re-copied items
+pub fn compute_history_stats(top: usize) -> Result<ClipboardHistoryStats, String> {
+    let history = get_clipboard_history_storage().lock().map_err(|e| e.to_string())?;
+    let mut counters = HISTORY_COUNTERS.lock().map_err(|e| e.to_string())?;
+
+    // Expired items are only dropped on the next capture, but are no longer listed
+    let now = chrono::Utc::now().timestamp();
+    let items: Vec<&ClipboardHistoryItem> = history.iter().filter(|item| !is_expired(item, now)).collect();
+
+    let mut counts_by_type = HashMap::new();
+    let mut total_size = 0;
+    for item in &items {
+        *counts_by_type.entry(item.content_type.clone()).or_insert(0) += 1;
+        total_size += history_item_size(item);
+    }
+
+    // Forget re-copy counts of items that were deleted in the meantime
+    counters.recopies.retain(|id, _| history.iter().any(|item| &item.id == id));
+
+    let mut most_recopied: Vec<RecopiedItem> = items.iter()
+        .filter_map(|item| {
+            let recopies = *counters.recopies.get(&item.id)?;
+            let preview = match &item.label {
+                Some(label) => label.clone(),
+                None if item.hide_preview => String::new(),
+                None => item.primary_content.chars().take(100).collect(),
+            };
+            Some(RecopiedItem {
+                id: item.id.clone(),
+                content_type: item.content_type.clone(),
+                preview,
+                copy_count: recopies + 1,
+            })
+        })
+        .collect();
+    most_recopied.sort_by_key(|item| std::cmp::Reverse(item.copy_count));
+    most_recopied.truncate(top);
+
+    Ok(ClipboardHistoryStats {
+        total_items: items.len(),
+        pinned_items: items.iter().filter(|item| item.pinned).count(),
+        counts_by_type,
+        total_size,
+        average_size: if items.is_empty() { 0.0 } else { total_size as f64 / items.len() as f64 },
+        most_recopied,
+        copies_per_hour: counters.copies_per_hour.to_vec(),
+        total_copies: counters.total_copies,
+        duplicate_hits: counters.duplicate_hits,
+        dedup_hit_rate: if counters.total_copies == 0 {
+            0.0
+        } else {
+            counters.duplicate_hits as f64 / counters.total_copies as f64
+        },
+        evictions: counters.evictions,
+    })
+}
+
+/// Fingerprint of the value placed on the clipboard by `copy_secret`, tagged with a unique token
+struct ActiveSecret {
+    token: u64,
+    fingerprint: u64,
+}
+
+// Only a hash of the secret is kept in memory, never the value itself
+static ACTIVE_SECRET: Mutex<Option<ActiveSecret>> = Mutex::new(None);
+static SECRET_TOKEN: AtomicU64 = AtomicU64::new(0);
+
+fn secret_fingerprint(value: &str) -> u64 {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Remember a secret that was just written to the clipboard and return its token
+pub fn register_active_secret(value: &str) -> u64 {
+    let token = SECRET_TOKEN.fetch_add(1, Ordering::SeqCst) + 1;
+    if let Ok(mut active) = ACTIVE_SECRET.lock() {
+        *active = Some(ActiveSecret { token, fingerprint: secret_fingerprint(value) });
+    }
+    token
+}
+
+/// Whether a secret written by `copy_secret` may still be on the clipboard
+pub fn has_active_secret() -> bool {
+    ACTIVE_SECRET.lock().map(|active| active.is_some()).unwrap_or(false)
+}
+
+/// Whether the value is the secret currently placed on the clipboard by `copy_secret`
+pub fn is_active_secret(value: &str) -> bool {
+    ACTIVE_SECRET.lock()
+        .map(|active| active.as_ref().is_some_and(|secret| secret.fingerprint == secret_fingerprint(value)))
+        .unwrap_or(false)
+}
+
+/// Forget the secret with the given token
+/// Returns true if it was still the active secret and `clipboard_text` still matches it
+pub fn take_active_secret(token: u64, clipboard_text: Option<&str>) -> bool {
+    let Ok(mut active) = ACTIVE_SECRET.lock() else {
+        return false;
+    };
+    match active.as_ref() {
+        Some(secret) if secret.token == token => {
+            let unchanged = clipboard_text.is_some_and(|text| secret.fingerprint == secret_fingerprint(text));
+            *active = None;
+            unchanged
+        }
+        // A newer secret replaced this one, leave it alone
+        _ => false,
+    }
+}
+
+/// Collect mode state; while active every new clipboard text is appended to `items`
+struct PasteStack {
+    active: bool,
+    items: Vec<String>,
+    last_seen: Option<String>, // Clipboard text at the last capture, so polling does not collect it twice
+}
+
+static PASTE_STACK: Mutex<PasteStack> = Mutex::new(PasteStack {
+    active: false,
+    items: Vec::new(),
+    last_seen: None,
+});
+
+fn paste_stack_snapshot(stack: &PasteStack) -> PasteStackState {
+    PasteStackState {
+        active: stack.active,
+        items: stack.items.clone(),
+    }
+}
+
+/// Current collect mode state
+pub fn paste_stack_state() -> Result<PasteStackState, String> {
+    let stack = PASTE_STACK.lock().map_err(|e| e.to_string())?;
+    Ok(paste_stack_snapshot(&stack))
+}
+
+/// Turn collect mode on with an empty stack
+/// `clipboard_text` is what is on the clipboard now; it is not collected
+pub fn start_paste_stack(clipboard_text: Option<String>) -> Result<PasteStackState, String> {
+    let mut stack = PASTE_STACK.lock().map_err(|e| e.to_string())?;
+    stack.active = true;
+    stack.items.clear();
+    stack.last_seen = clipboard_text;
+    Ok(paste_stack_snapshot(&stack))
+}
+
+/// Turn collect mode off and return what was collected
+pub fn stop_paste_stack() -> Result<PasteStackState, String> {
+    let mut stack = PASTE_STACK.lock().map_err(|e| e.to_string())?;
+    let collected = PasteStackState {
+        active: false,
+        items: std::mem::take(&mut stack.items),
+    };
+    stack.active = false;
+    stack.last_seen = None;
+    Ok(collected)
+}
+
+/// Append new clipboard text to the paste stack while collect mode is active
+/// Returns the new state if the stack changed
+fn collect_into_paste_stack(clipboard_text: Option<&str>) -> Option<PasteStackState> {
+    let mut stack = PASTE_STACK.lock().ok()?;
+    let text = clipboard_text?;
+    if !stack.active || stack.last_seen.as_deref() == Some(text) {
+        return None;
+    }
+
+    stack.last_seen = Some(text.to_string());
+    if is_active_secret(text) {
+        return None;
+    }
+    stack.items.push(text.to_string());
+    Some(paste_stack_snapshot(&stack))
+}
+
+/// Take the next item off the paste stack to restore it to the clipboard
+/// The item is remembered as seen so the capture path does not collect it again
+pub fn pop_paste_stack_item(order: PasteStackOrder) -> Result<(Option<String>, PasteStackState), String> {
+    let mut stack = PASTE_STACK.lock().map_err(|e| e.to_string())?;
+    let item = match order {
+        PasteStackOrder::Fifo if !stack.items.is_empty() => Some(stack.items.remove(0)),
+        PasteStackOrder::Fifo => None,
+        PasteStackOrder::Lifo => stack.items.pop(),
+    };
+    if item.is_some() {
+        stack.last_seen = item.clone();
+    }
+    Ok((item, paste_stack_snapshot(&stack)))
+}
+
+/// Join the paste stack into a single value to put on the clipboard
+/// The joined value is remembered as seen so the capture path does not collect it again
+pub fn join_paste_stack(separator: &str) -> Result<String, String> {
+    let mut stack = PASTE_STACK.lock().map_err(|e| e.to_string())?;
+    if stack.items.is_empty() {
+        return Err("Paste stack is empty".to_string());
+    }
+    let joined = stack.items.join(separator);
+    stack.last_seen = Some(joined.clone());
+    Ok(joined)
 }
 
 pub fn detect_content_type(content: &str) -> String {
@@ -30,6 +381,8 @@ pub fn detect_content_type(content: &str) -> String {
         "Email".to_string()
     } else if content.lines().count() > 1 {
         "Multiline Text".to_string()
+    } else if let Some(calculation) = evaluate_calculation(content) {
+        if calculation.unit.is_some() { "Unit Conversion" } else { "Calculation" }.to_string()
     } else if content.chars().all(|c| c.is_ascii_digit() || c == '.' || c == '-' || c == '+') {
         "Number".to_string()
     } else {
@@ -37,6 +390,64 @@ pub fn detect_content_type(content: &str) -> String {
     }
 }
 
+/// Create a history item for text content
+pub fn create_text_history_item(id: String, timestamp: String, formats: Vec<ClipboardFormat>, content: &str) -> ClipboardHistoryItem {
+    let mut item = ClipboardHistoryItem {
+        id,
+        timestamp,
+        formats,
+        primary_content: String::new(),
+        content_type: String::new(),
+        plain_text: None,
+        html_content: None,
+        rtf_content: None,
+        image_data: None,
+        image_format: None,
+        image_dimensions: None,
+        image_hash: None,
+        file_paths: None,
+        file_list: None,
+        custom_formats: None,
+        label: None,
+        note: None,
+        pinned: false,
+        source_app: None,
+        hide_preview: false,
+        expires_at: None,
+        calculation: None,
+    };
+    set_text_content(&mut item, content);
+    item
+}
+
+/// Set the text content of a history item and re-derive its content type and text formats
+pub fn set_text_content(item: &mut ClipboardHistoryItem, content: &str) {
+    let content_type = detect_content_type(content);
+
+    // Extract different format types
+    item.plain_text = if content_type != "Image" { Some(content.to_string()) } else { None };
+    item.html_content = if content.contains("<html>") || content.contains("<!DOCTYPE") || content.contains("<div") {
+        Some(content.to_string())
+    } else { None };
+    item.rtf_content = if content.starts_with("{\\rtf") {
+        Some(content.to_string())
+    } else { None };
+
+    // Check for file paths (improved detection)
+    item.file_paths = if is_file_list(content) {
+        Some(content.lines().filter(|line| !line.trim().is_empty()).map(|s| s.to_string()).collect())
+    } else { None };
+    item.file_list = if item.file_paths.is_some() { Some(content.to_string()) } else { None };
+
+    // Offer the result of calculations and unit conversions as a derived value
+    item.calculation = if content_type == "Calculation" || content_type == "Unit Conversion" {
+        evaluate_calculation(content)
+    } else { None };
+
+    item.primary_content = content.to_string();
+    item.content_type = content_type;
+}
+
 pub fn process_image_data(image_data: &[u8], width: u32, height: u32) -> Result<(String, String, (u32, u32)), String> {
     // Convert RGBA data to PNG format for storage
     let img = image::RgbaImage::from_raw(width, height, image_data.to_vec())
@@ -54,6 +465,728 @@ pub fn process_image_data(image_data: &[u8], width: u32, height: u32) -> Result<
     Ok((base64_data, "png".to_string(), dimensions))
 }
 
+/// Decode an encoded image (PNG, JPEG, ...) and drop all of its metadata
+/// The EXIF orientation is applied to the pixels first so the image still displays upright
+pub fn decode_image_without_metadata(image_bytes: &[u8]) -> Result<DynamicImage, String> {
+    let reader = ImageReader::new(Cursor::new(image_bytes))
+        .with_guessed_format()
+        .map_err(|e| format!("Failed to detect image format: {}", e))?;
+
+    let mut decoder = reader.into_decoder()
+        .map_err(|e| format!("Failed to load image: {}", e))?;
+    let orientation = decoder.orientation()
+        .unwrap_or(image::metadata::Orientation::NoTransforms);
+
+    // Only the pixel data survives decoding, so EXIF, XMP and ICC data are discarded here
+    let mut img = DynamicImage::from_decoder(decoder)
+        .map_err(|e| format!("Failed to load image: {}", e))?;
+    img.apply_orientation(orientation);
+
+    Ok(img)
+}
+
+/// Look up a clipboard history item by ID
+pub fn get_history_item(id: &str) -> Result<ClipboardHistoryItem, String> {
+    let storage = get_clipboard_history_storage();
+    let history = storage.lock().map_err(|e| e.to_string())?;
+    history.iter()
+        .find(|item| item.id == id)
+        .cloned()
+        .ok_or_else(|| format!("Clipboard history item '{}' not found", id))
+}
+
+/// Load the image stored on a clipboard history item
+pub fn load_history_image(id: &str) -> Result<DynamicImage, String> {
+    let image_data = get_history_item(id)?
+        .image_data
+        .ok_or_else(|| format!("Clipboard history item '{}' does not contain an image", id))?;
+
+    let image_bytes = general_purpose::STANDARD.decode(&image_data)
+        .map_err(|e| format!("Failed to decode base64 image: {}", e))?;
+    decode_image_without_metadata(&image_bytes)
+}
+
+/// Resize an image into the given box using the requested fit mode
+pub fn resize_image(img: &DynamicImage, width: u32, height: u32, fit: ImageFitMode) -> Result<DynamicImage, String> {
+    if width == 0 || height == 0 {
+        return Err("Image dimensions must be greater than zero".to_string());
+    }
+
+    Ok(match fit {
+        ImageFitMode::Contain => img.resize(width, height, FilterType::Lanczos3),
+        ImageFitMode::Cover => img.resize_to_fill(width, height, FilterType::Lanczos3),
+        ImageFitMode::Fill => img.resize_exact(width, height, FilterType::Lanczos3),
+    })
+}
+
+/// Crop a region out of an image, rejecting regions that fall outside of it
+pub fn crop_image(img: &DynamicImage, x: u32, y: u32, width: u32, height: u32) -> Result<DynamicImage, String> {
+    if width == 0 || height == 0 {
+        return Err("Crop dimensions must be greater than zero".to_string());
+    }
+
+    let fits_horizontally = x.checked_add(width).is_some_and(|right| right <= img.width());
+    let fits_vertically = y.checked_add(height).is_some_and(|bottom| bottom <= img.height());
+    if !fits_horizontally || !fits_vertically {
+        return Err(format!(
+            "Crop region {}x{} at ({}, {}) is outside the {}x{} image",
+            width, height, x, y, img.width(), img.height()
+        ));
+    }
+
+    Ok(img.crop_imm(x, y, width, height))
+}
+
+/// Rotate an image clockwise by a multiple of 90 degrees
+pub fn rotate_image(img: &DynamicImage, degrees: u32) -> Result<DynamicImage, String> {
+    match degrees % 360 {
+        0 => Ok(img.clone()),
+        90 => Ok(img.rotate90()),
+        180 => Ok(img.rotate180()),
+        270 => Ok(img.rotate270()),
+        _ => Err(format!("Unsupported rotation of {} degrees (use 90, 180 or 270)", degrees)),
+    }
+}
+
+/// Parse a user supplied image format name
+pub fn parse_image_format(format: &str) -> Result<ImageFormat, String> {
+    match format.to_lowercase().as_str() {
+        "png" => Ok(ImageFormat::Png),
+        "jpg" | "jpeg" => Ok(ImageFormat::Jpeg),
+        "bmp" => Ok(ImageFormat::Bmp),
+        "gif" => Ok(ImageFormat::Gif),
+        "tiff" | "tif" => Ok(ImageFormat::Tiff),
+        _ => Err(format!("Unsupported image format: {}", format)),
+    }
+}
+
+/// Encode an image in the given format; quality only applies to JPEG
+pub fn encode_image(img: &DynamicImage, format: ImageFormat, quality: Option<u8>) -> Result<Vec<u8>, String> {
+    let mut encoded = Vec::new();
+    let mut cursor = Cursor::new(&mut encoded);
+
+    match format {
+        ImageFormat::Jpeg => {
+            // JPEG has no alpha channel
+            let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(
+                &mut cursor,
+                quality.unwrap_or(90).clamp(1, 100),
+            );
+            img.to_rgb8().write_with_encoder(encoder)
+                .map_err(|e| format!("Failed to encode image as JPEG: {}", e))?;
+        }
+        _ => {
+            img.write_to(&mut cursor, format)
+                .map_err(|e| format!("Failed to encode image as {:?}: {}", format, e))?;
+        }
+    }
+
+    Ok(encoded)
+}
+
+/// Clipboard contents read in a single pass
+pub struct ClipboardSnapshot {
+    pub text: Option<String>,
+    pub image: Option<ClipboardImage>,
+}
+
+// Fingerprint of the clipboard at the last capture, so polling only counts a copy once
+static LAST_CAPTURED: Mutex<Option<u64>> = Mutex::new(None);
+
+/// Hash of the text and image in a snapshot
+fn snapshot_fingerprint(snapshot: &ClipboardSnapshot) -> u64 {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = DefaultHasher::new();
+    snapshot.text.hash(&mut hasher);
+    if let Some(image) = &snapshot.image {
+        image.width.hash(&mut hasher);
+        image.height.hash(&mut hasher);
+        image.rgba.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// Fingerprint of a snapshot holding only the given text
+fn text_fingerprint(text: &str) -> u64 {
+    snapshot_fingerprint(&ClipboardSnapshot {
+        text: Some(text.to_string()),
+        image: None,
+    })
+}
+
+/// Remember a snapshot fingerprint as captured; returns false if it matches the previous capture
+fn mark_snapshot_captured(fingerprint: u64) -> bool {
+    match LAST_CAPTURED.lock() {
+        Ok(mut last) => last.replace(fingerprint) != Some(fingerprint),
+        Err(_) => true,
+    }
+}
+
+/// Read the text and image currently on the clipboard, once each
+pub fn read_clipboard_snapshot(backend: &dyn ClipboardBackend) -> ClipboardSnapshot {
+    ClipboardSnapshot {
+        text: backend.read_text().ok().filter(|text| !text.is_empty()),
+        image: backend.read_image().ok(),
+    }
+}
+
+/// Detect every format currently available on the clipboard
+pub fn detect_clipboard_formats(backend: &dyn ClipboardBackend) -> Vec<ClipboardFormat> {
+    formats_from_snapshot(&read_clipboard_snapshot(backend))
+}
+
+/// Detect every format available in a clipboard snapshot
+/// Image formats carry no raw data; a captured image is kept once, as PNG, in its history item
+pub fn formats_from_snapshot(snapshot: &ClipboardSnapshot) -> Vec<ClipboardFormat> {
+    let mut formats = Vec::new();
+    
+    // Check for image data first
+    if let Some(image) = &snapshot.image {
+        formats.push(ClipboardFormat {
+            format_name: "Image (RGBA)".to_string(),
+            format_type: "image/rgba".to_string(),
+            data_size: image.rgba.len(),
+            content_preview: format!("{}x{} pixels", image.width, image.height),
+            is_available: true,
+            raw_data: None,
+        });
+        
+        formats.push(ClipboardFormat {
+            format_name: "Image (PNG)".to_string(),
+            format_type: "image/png".to_string(),
+            data_size: image.rgba.len(),
+            content_preview: "PNG format available".to_string(),
+            is_available: true,
+            raw_data: None,
+        });
+        
+        formats.push(ClipboardFormat {
+            format_name: "Image (JPEG)".to_string(),
+            format_type: "image/jpeg".to_string(),
+            data_size: image.rgba.len(),
+            content_preview: "JPEG format available".to_string(),
+            is_available: true,
+            raw_data: None,
+        });
+        
+        formats.push(ClipboardFormat {
+            format_name: "Image (Bitmap)".to_string(),
+            format_type: "image/bmp".to_string(),
+            data_size: image.rgba.len(),
+            content_preview: "Bitmap format available".to_string(),
+            is_available: true,
+            raw_data: None,
+        });
+    }
+    
+    // Try to get text format and analyze all possible text-based formats
+    if let Some(text_content) = &snapshot.text && !text_content.is_empty() {
+        // Plain text format (available on all platforms)
+        formats.push(ClipboardFormat {
+            format_name: "Plain Text".to_string(),
+            format_type: "text/plain".to_string(),
+            data_size: text_content.len(),
+            content_preview: if text_content.len() > 100 {
+                format!("{}...", &text_content[..100])
+            } else {
+                text_content.clone()
+            },
+            is_available: true,
+            raw_data: Some(general_purpose::STANDARD.encode(text_content)),
+        });
+
+        // Unicode text (cross-platform)
+        formats.push(ClipboardFormat {
+            format_name: "Unicode Text (UTF-8)".to_string(),
+            format_type: "text/unicode".to_string(),
+            data_size: text_content.chars().count() * 4,
+            content_preview: "Unicode version of text".to_string(),
+            is_available: true,
+            raw_data: Some(general_purpose::STANDARD.encode(text_content)),
+        });
+
+        // UTF-16 representation
+        let utf16_bytes: Vec<u8> = text_content.encode_utf16()
+            .flat_map(|c| c.to_le_bytes().to_vec())
+            .collect();
+        formats.push(ClipboardFormat {
+            format_name: "Unicode Text (UTF-16LE)".to_string(),
+            format_type: "text/utf-16le".to_string(),
+            data_size: utf16_bytes.len(),
+            content_preview: "UTF-16 Little Endian encoding".to_string(),
+            is_available: true,
+            raw_data: Some(general_purpose::STANDARD.encode(&utf16_bytes)),
+        });
+
+        // HTML detection and format
+        if text_content.contains("<html>") || text_content.contains("<!DOCTYPE") || 
+           text_content.contains("<div") || text_content.contains("<p") ||
+           text_content.contains("<span") || text_content.contains("<br") {
+            formats.push(ClipboardFormat {
+                format_name: "HTML".to_string(),
+                format_type: "text/html".to_string(),
+                data_size: text_content.len(),
+                content_preview: "HTML content detected".to_string(),
+                is_available: true,
+                raw_data: Some(general_purpose::STANDARD.encode(text_content)),
+            });
+            
+            // HTML Fragment (Windows specific)
+            formats.push(ClipboardFormat {
+                format_name: "HTML Fragment".to_string(),
+                format_type: "text/html-fragment".to_string(),
+                data_size: text_content.len(),
+                content_preview: "HTML Fragment format".to_string(),
+                is_available: true,
+                raw_data: Some(general_purpose::STANDARD.encode(text_content)),
+            });
+        }
+
+        // RTF (Rich Text Format) detection
+        if text_content.starts_with("{\\rtf") {
+            formats.push(ClipboardFormat {
+                format_name: "Rich Text Format".to_string(),
+                format_type: "text/rtf".to_string(),
+                data_size: text_content.len(),
+                content_preview: "Rich Text Format content".to_string(),
+                is_available: true,
+                raw_data: Some(general_purpose::STANDARD.encode(text_content)),
+            });
+        }
+
+        // URL detection
+        if text_content.starts_with("http://") || text_content.starts_with("https://") ||
+           text_content.starts_with("ftp://") || text_content.starts_with("ftps://") {
+            formats.push(ClipboardFormat {
+                format_name: "URL".to_string(),
+                format_type: "text/uri-list".to_string(),
+                data_size: text_content.len(),
+                content_preview: "URL format".to_string(),
+                is_available: true,
+                raw_data: Some(general_purpose::STANDARD.encode(text_content)),
+            });
+            
+            formats.push(ClipboardFormat {
+                format_name: "Internet Shortcut".to_string(),
+                format_type: "application/x-mswinurl".to_string(),
+                data_size: text_content.len(),
+                content_preview: "Windows Internet Shortcut".to_string(),
+                is_available: true,
+                raw_data: Some(general_purpose::STANDARD.encode(text_content)),
+            });
+        }
+
+        // File path detection (improved without disk I/O)
+        if is_file_list(text_content) {
+            let lines: Vec<&str> = text_content.lines().filter(|line| !line.trim().is_empty()).collect();
+            formats.push(ClipboardFormat {
+                format_name: "File Drop List".to_string(),
+                format_type: "text/uri-list".to_string(),
+                data_size: text_content.len(),
+                content_preview: format!("{} file(s)", lines.len()),
+                is_available: true,
+                raw_data: Some(general_purpose::STANDARD.encode(text_content)),
+            });
+            
+            formats.push(ClipboardFormat {
+                format_name: "Shell IDList Array".to_string(),
+                format_type: "application/x-shell-idlist".to_string(),
+                data_size: text_content.len(),
+                content_preview: "Windows Shell file list".to_string(),
+                is_available: true,
+                raw_data: Some(general_purpose::STANDARD.encode(text_content)),
+            });
+        }
+
+        // JSON detection
+        if ((text_content.trim().starts_with("{") && text_content.trim().ends_with("}")) ||
+            (text_content.trim().starts_with("[") && text_content.trim().ends_with("]")))
+            && serde_json::from_str::<serde_json::Value>(text_content).is_ok()
+        {
+            formats.push(ClipboardFormat {
+                format_name: "JSON".to_string(),
+                format_type: "application/json".to_string(),
+                data_size: text_content.len(),
+                content_preview: "Valid JSON data".to_string(),
+                is_available: true,
+                raw_data: Some(general_purpose::STANDARD.encode(text_content)),
+            });
+        }
+
+        // XML detection
+        if text_content.trim().starts_with("<?xml") || 
+           (text_content.trim().starts_with("<") && text_content.trim().ends_with(">")) {
+            formats.push(ClipboardFormat {
+                format_name: "XML".to_string(),
+                format_type: "application/xml".to_string(),
+                data_size: text_content.len(),
+                content_preview: "XML content detected".to_string(),
+                is_available: true,
+                raw_data: Some(general_purpose::STANDARD.encode(text_content)),
+            });
+        }
+
+        // CSV detection
+        if text_content.contains(",") && text_content.lines().count() > 1 {
+            let lines: Vec<&str> = text_content.lines().collect();
+            if lines.len() > 1 && lines.iter().all(|line| line.contains(",")) {
+                formats.push(ClipboardFormat {
+                    format_name: "CSV".to_string(),
+                    format_type: "text/csv".to_string(),
+                    data_size: text_content.len(),
+                    content_preview: format!("CSV with {} rows", lines.len()),
+                    is_available: true,
+                    raw_data: Some(general_purpose::STANDARD.encode(text_content)),
+                });
+            }
+        }
+
+        // Email detection
+        if text_content.contains("@") && text_content.contains(".") && 
+           !text_content.contains(" ") && text_content.lines().count() == 1 {
+            formats.push(ClipboardFormat {
+                format_name: "Email Address".to_string(),
+                format_type: "text/x-email".to_string(),
+                data_size: text_content.len(),
+                content_preview: "Email address format".to_string(),
+                is_available: true,
+                raw_data: Some(general_purpose::STANDARD.encode(text_content)),
+            });
+        }
+
+        // Code detection (basic)
+        if text_content.contains("function") || text_content.contains("class") ||
+           text_content.contains("import") || text_content.contains("const") ||
+           text_content.contains("def ") || text_content.contains("public class") {
+            formats.push(ClipboardFormat {
+                format_name: "Source Code".to_string(),
+                format_type: "text/x-source-code".to_string(),
+                data_size: text_content.len(),
+                content_preview: "Programming code detected".to_string(),
+                is_available: true,
+                raw_data: Some(general_purpose::STANDARD.encode(text_content)),
+            });
+        }
+
+        // Markdown detection
+        if text_content.contains("# ") || text_content.contains("## ") ||
+           text_content.contains("**") || text_content.contains("```") {
+            formats.push(ClipboardFormat {
+                format_name: "Markdown".to_string(),
+                format_type: "text/markdown".to_string(),
+                data_size: text_content.len(),
+                content_preview: "Markdown content detected".to_string(),
+                is_available: true,
+                raw_data: Some(general_purpose::STANDARD.encode(text_content)),
+            });
+        }
+
+        // Base64 detection
+        if text_content.len() > 20 && text_content.chars().all(|c| {
+            c.is_ascii_alphanumeric() || c == '+' || c == '/' || c == '='
+        }) && text_content.len() % 4 == 0 {
+            formats.push(ClipboardFormat {
+                format_name: "Base64 Encoded Data".to_string(),
+                format_type: "application/base64".to_string(),
+                data_size: text_content.len(),
+                content_preview: "Base64 encoded content".to_string(),
+                is_available: true,
+                raw_data: Some(general_purpose::STANDARD.encode(text_content)),
+            });
+        }
+    }
+
+    // Add system-specific formats
+    #[cfg(target_os = "windows")]
+    {
+        // Windows-specific formats would go here
+        // These are conceptual as tauri-plugin-clipboard-manager may not expose them directly
+        if !formats.is_empty() {
+            formats.push(ClipboardFormat {
+                format_name: "Windows Locale".to_string(),
+                format_type: "application/x-windows-locale".to_string(),
+                data_size: 4,
+                content_preview: "System locale information".to_string(),
+                is_available: true,
+                raw_data: None,
+            });
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        // macOS-specific formats
+        if !formats.is_empty() {
+            formats.push(ClipboardFormat {
+                format_name: "macOS Pasteboard Type".to_string(),
+                format_type: "com.apple.pasteboard.promised-file-url".to_string(),
+                data_size: 0,
+                content_preview: "macOS pasteboard metadata".to_string(),
+                is_available: true,
+                raw_data: None,
+            });
+        }
+    }
+
+    // If no formats detected, show empty state
+    if formats.is_empty() {
+        formats.push(ClipboardFormat {
+            format_name: "Empty".to_string(),
+            format_type: "empty".to_string(),
+            data_size: 0,
+            content_preview: "Clipboard is empty".to_string(),
+            is_available: false,
+            raw_data: None,
+        });
+    }
+
+    formats
+}
+
+/// Add text content to the history unless it is a duplicate or a secret
+/// Returns the resulting history change, if any
+pub fn record_text_in_history(backend: &dyn ClipboardBackend, content: &str) -> Option<ClipboardHistoryDelta> {
+    let snapshot = read_clipboard_snapshot(backend);
+    // Text the app just copied is the same clipboard change the monitor sees next
+    let change = if snapshot.text.as_deref() == Some(content) {
+        snapshot_fingerprint(&snapshot)
+    } else {
+        text_fingerprint(content)
+    };
+
+    // Formats could capture a secret still on the clipboard
+    let formats = if has_active_secret() {
+        Vec::new()
+    } else {
+        formats_from_snapshot(&snapshot)
+    };
+    record_text_with_formats(content, formats, None, change)
+}
+
+/// Add text derived from other content, such as a decoded barcode, to the history
+/// Its formats describe the text itself rather than what is on the clipboard
+pub fn record_derived_text_in_history(content: &str) -> Option<ClipboardHistoryDelta> {
+    let formats = formats_from_snapshot(&ClipboardSnapshot {
+        text: Some(content.to_string()),
+        image: None,
+    });
+    record_text_with_formats(content, formats, None, text_fingerprint(content))
+}
+
+/// Add text content with already detected formats to the history unless it is a duplicate or a secret
+fn record_text_with_formats(
+    content: &str,
+    formats: Vec<ClipboardFormat>,
+    source_app: Option<ClipboardSourceApp>,
+    change: u64,
+) -> Option<ClipboardHistoryDelta> {
+    insert_text_item(text_capture_item(content, formats, source_app)?, change)
+}
+
+/// Build the history item for copied text and apply the capture rules to it
+/// Returns None for empty text, secrets and text a rule ignores
+fn text_capture_item(
+    content: &str,
+    formats: Vec<ClipboardFormat>,
+    source_app: Option<ClipboardSourceApp>,
+) -> Option<ClipboardHistoryItem> {
+    if content.is_empty() {
+        return None;
+    }
+
+    // Values written by copy_secret are never recorded
+    if is_active_secret(content) {
+        return None;
+    }
+
+    let timestamp = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC").to_string();
+    let id = generate_clipboard_id(content, &timestamp);
+    
+    let mut history_item = create_text_history_item(id, timestamp, formats, content);
+    history_item.source_app = source_app;
+    apply_capture_rules(history_item)
+}
+
+/// Add a text item to the front of the history unless its content is already there
+fn insert_text_item(history_item: ClipboardHistoryItem, change: u64) -> Option<ClipboardHistoryDelta> {
+    let storage = get_clipboard_history_storage();
+    let mut history = storage.lock().ok()?;
+
+    // Check if this content already exists (avoid duplicates)
+    if let Some(existing) = history.iter().find(|item| item.primary_content == history_item.primary_content) {
+        record_copy(change, Some(&existing.id));
+        return None;
+    }
+    record_copy(change, None);
+
+    // Add to front of history, evicting the oldest unpinned items beyond the limit
+    let inserted = vec![history_item.id.clone()];
+    let evicted = push_history_item(&mut history, history_item);
+    Some(history_delta(inserted, evicted, Vec::new()))
+}
+
+/// Hash of an image's dimensions and raw pixels
+fn image_fingerprint(image: &ClipboardImage) -> String {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = DefaultHasher::new();
+    image.width.hash(&mut hasher);
+    image.height.hash(&mut hasher);
+    image.rgba.hash(&mut hasher);
+    format!("{:x}", hasher.finish())
+}
+
+/// Add a copied image to the history unless the same pixels are already there
+/// Only new images the capture rules keep are encoded as PNG
+fn record_image(
+    image: &ClipboardImage,
+    formats: Vec<ClipboardFormat>,
+    source_app: Option<ClipboardSourceApp>,
+    change: u64,
+) -> Option<ClipboardHistoryDelta> {
+    let storage = get_clipboard_history_storage();
+    let image_hash = image_fingerprint(image);
+
+    // Rules run before deduplication and encoding, as for text, so ignored images cost nothing
+    let timestamp = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC").to_string();
+    let history_item = ClipboardHistoryItem {
+        id: generate_clipboard_id("image_data", &timestamp),
+        timestamp,
+        formats,
+        primary_content: format!("Image {}x{}", image.width, image.height),
+        content_type: "Image".to_string(),
+        plain_text: None,
+        html_content: None,
+        rtf_content: None,
+        image_data: None,
+        image_format: None,
+        image_dimensions: Some((image.width, image.height)),
+        image_hash: Some(image_hash.clone()),
+        file_paths: None,
+        file_list: None,
+        custom_formats: None,
+        label: None,
+        note: None,
+        pinned: false,
+        source_app,
+        hide_preview: false,
+        expires_at: None,
+        calculation: None,
+    };
+    let mut history_item = apply_capture_rules(history_item)?;
+
+    // Check if this image already exists (avoid duplicates)
+    {
+        let history = storage.lock().ok()?;
+        if let Some(existing) = history.iter().find(|item| item.image_hash.as_deref() == Some(image_hash.as_str())) {
+            record_copy(change, Some(&existing.id));
+            return None;
+        }
+    }
+
+    let (base64_data, format, _) = match process_image_data(&image.rgba, image.width, image.height) {
+        Ok(processed) => processed,
+        Err(e) => {
+            eprintln!("Failed to process image data: {}", e);
+            return None;
+        }
+    };
+    history_item.image_data = Some(base64_data);
+    history_item.image_format = Some(format);
+
+    let mut history = storage.lock().ok()?;
+    record_copy(change, None);
+    let inserted = vec![history_item.id.clone()];
+    let evicted = push_history_item(&mut history, history_item);
+    Some(history_delta(inserted, evicted, Vec::new()))
+}
+
+/// Changes made by one pass of the capture path
+pub struct ClipboardCapture {
+    pub history: Vec<ClipboardHistoryDelta>,
+    pub paste_stack: Option<PasteStackState>, // New paste stack state if collect mode appended the text
+}
+
+/// Record the current clipboard contents (image first, then text) in the history
+/// and, in collect mode, append new text to the paste stack
+pub fn capture_clipboard(backend: &dyn ClipboardBackend) -> ClipboardCapture {
+    let mut deltas = Vec::new();
+
+    // Drop items whose rule-assigned expiry has passed
+    if let Ok(mut history) = get_clipboard_history_storage().lock() {
+        let expired = remove_expired_items(&mut history);
+        if !expired.is_empty() {
+            deltas.push(history_delta(Vec::new(), expired, Vec::new()));
+        }
+    }
+
+    // Read the clipboard once; formats are shared by the image and text entries
+    let snapshot = read_clipboard_snapshot(backend);
+
+    // Polling sees the same content until the next copy, which was already recorded
+    let change = snapshot_fingerprint(&snapshot);
+    if !mark_snapshot_captured(change) {
+        return ClipboardCapture {
+            history: deltas,
+            paste_stack: None,
+        };
+    }
+    let source_app = if snapshot.text.is_some() || snapshot.image.is_some() {
+        detect_source_app()
+    } else {
+        None
+    };
+    let formats = if has_active_secret() {
+        Vec::new()
+    } else {
+        formats_from_snapshot(&snapshot)
+    };
+
+    // Rules run before the text reaches any consumer, so ignored copies are not collected either
+    let text_item = snapshot.text.as_deref()
+        .and_then(|text| text_capture_item(text, formats.clone(), source_app.clone()));
+    let paste_stack = collect_into_paste_stack(text_item.as_ref().map(|item| item.primary_content.as_str()));
+
+    // Check for image data first
+    if let Some(image) = &snapshot.image {
+        deltas.extend(record_image(image, formats, source_app, change));
+    }
+    
+    // Add the current text content to history if it's new
+    if let Some(text_item) = text_item {
+        deltas.extend(insert_text_item(text_item, change));
+    }
+
+    ClipboardCapture {
+        history: deltas,
+        paste_stack,
+    }
+}
+
+/// Read the clipboard image as a decoded image
+pub fn read_clipboard_image(backend: &dyn ClipboardBackend) -> Result<DynamicImage, String> {
+    let image = backend.read_image()?;
+    let rgba = image::RgbaImage::from_raw(image.width, image.height, image.rgba)
+        .ok_or("Failed to create image from raw data")?;
+    Ok(DynamicImage::ImageRgba8(rgba))
+}
+
+/// Write a decoded image to the clipboard as raw RGBA pixels
+pub fn write_clipboard_image(backend: &dyn ClipboardBackend, img: &DynamicImage) -> Result<(), String> {
+    // Convert to RGBA format for clipboard
+    let rgba_img = img.to_rgba8();
+    let (width, height) = rgba_img.dimensions();
+    
+    backend.write_image(&ClipboardImage {
+        width,
+        height,
+        rgba: rgba_img.into_raw(),
+    })
+}
+
 pub fn generate_clipboard_id(content: &str, timestamp: &str) -> String {
     use std::collections::hash_map::DefaultHasher;
     use std::hash::{Hash, Hasher};
@@ -95,6 +1228,52 @@ pub fn is_file_list(content: &str) -> bool {
     path_like_count >= threshold && path_like_count > 0
 }
 
+/// Render text as a QR code image with square modules of at least `size` pixels overall
+pub fn render_qr_code(content: &str, size: u32, error_correction: Option<&str>) -> Result<DynamicImage, String> {
+    use qrcode::{EcLevel, QrCode};
+
+    if content.is_empty() {
+        return Err("Cannot create a QR code from empty content".to_string());
+    }
+
+    let ec_level = match error_correction.map(|level| level.to_uppercase()).as_deref() {
+        None | Some("M") => EcLevel::M,
+        Some("L") => EcLevel::L,
+        Some("Q") => EcLevel::Q,
+        Some("H") => EcLevel::H,
+        Some(other) => return Err(format!("Unknown error correction level: {} (use L, M, Q or H)", other)),
+    };
+
+    let code = QrCode::with_error_correction_level(content.as_bytes(), ec_level)
+        .map_err(|e| format!("Failed to create QR code: {}", e))?;
+    let img = code.render::<image::Luma<u8>>()
+        .min_dimensions(size, size)
+        .quiet_zone(true)
+        .build();
+
+    Ok(DynamicImage::ImageLuma8(img))
+}
+
+/// Decode all QR codes and barcodes found in an image
+pub fn decode_barcodes(img: &DynamicImage) -> Result<Vec<DecodedBarcode>, String> {
+    let luma = img.to_luma8();
+    let (width, height) = luma.dimensions();
+
+    // rxing reports "nothing found" as an error; anything else is a real failure
+    let results = match rxing::helpers::detect_multiple_in_luma(luma.into_raw(), width, height) {
+        Ok(results) => results,
+        Err(rxing::Exceptions::NotFoundException(_)) => Vec::new(),
+        Err(e) => return Err(format!("Failed to decode barcodes: {}", e)),
+    };
+
+    Ok(results.iter()
+        .map(|result| DecodedBarcode {
+            format: result.getBarcodeFormat().to_string(),
+            text: result.getText().to_string(),
+        })
+        .collect())
+}
+
 /// Check if a single line looks like a file path without disk I/O
 fn is_path_like(line: &str) -> bool {
     let trimmed = line.trim();
@@ -107,7 +1286,7 @@ fn is_path_like(line: &str) -> bool {
     // Windows absolute paths (C:\, D:\, etc.)
     if trimmed.len() >= 3 && trimmed.chars().nth(1) == Some(':') &&
        trimmed.chars().nth(2) == Some('\\') &&
-       trimmed.chars().nth(0).map_or(false, |c| c.is_ascii_alphabetic()) {
+       trimmed.chars().next().is_some_and(|c| c.is_ascii_alphabetic()) {
         return true;
     }
     
@@ -0,0 +1,112 @@
+//! Clipboard worker thread
+//!
+//! All clipboard access is serialized through a single thread that owns the
+//! clipboard backend. Commands queue requests and await the reply, so slow
+//! clipboard I/O and image encoding never block the async runtime. Reads that
+//! are queued back to back are answered from a single backend call.
+
+use crate::features::clipboard::backend::{ClipboardBackend, ClipboardBackendState, ClipboardImage};
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::sync::Arc;
+use std::sync::mpsc;
+use tokio::sync::oneshot;
+
+type Job = Box<dyn FnOnce(&dyn ClipboardBackend) + Send>;
+
+enum ClipboardRequest {
+    ReadText(oneshot::Sender<Result<String, String>>),
+    ReadImage(oneshot::Sender<Result<Arc<ClipboardImage>, String>>),
+    Run(Job),
+}
+
+/// Handle to the clipboard worker thread, managed as Tauri state
+#[derive(Clone)]
+pub struct ClipboardWorker {
+    sender: mpsc::Sender<ClipboardRequest>,
+}
+
+impl ClipboardWorker {
+    /// Start the worker thread that owns the given backend
+    pub fn spawn(backend: ClipboardBackendState) -> Self {
+        let (sender, receiver) = mpsc::channel();
+
+        std::thread::Builder::new()
+            .name("clipboard-worker".to_string())
+            .spawn(move || run_worker(backend, receiver))
+            .expect("failed to spawn clipboard worker thread");
+
+        Self { sender }
+    }
+
+    /// Read the clipboard text
+    pub async fn read_text(&self) -> Result<String, String> {
+        let (reply, response) = oneshot::channel();
+        self.send(ClipboardRequest::ReadText(reply))?;
+        response.await.map_err(|_| worker_stopped())?
+    }
+
+    /// Read the clipboard image
+    pub async fn read_image(&self) -> Result<Arc<ClipboardImage>, String> {
+        let (reply, response) = oneshot::channel();
+        self.send(ClipboardRequest::ReadImage(reply))?;
+        response.await.map_err(|_| worker_stopped())?
+    }
+
+    /// Run a job with exclusive access to the clipboard backend on the worker thread
+    /// Use this for writes and for anything that does heavy work such as image encoding
+    pub async fn run<T, F>(&self, job: F) -> Result<T, String>
+    where
+        T: Send + 'static,
+        F: FnOnce(&dyn ClipboardBackend) -> Result<T, String> + Send + 'static,
+    {
+        let (reply, response) = oneshot::channel();
+        self.send(ClipboardRequest::Run(Box::new(move |backend| {
+            let _ = reply.send(job(backend));
+        })))?;
+        response.await.map_err(|_| worker_stopped())?
+    }
+
+    fn send(&self, request: ClipboardRequest) -> Result<(), String> {
+        self.sender.send(request).map_err(|_| worker_stopped())
+    }
+}
+
+fn worker_stopped() -> String {
+    "Clipboard worker is not running".to_string()
+}
+
+fn run_worker(backend: ClipboardBackendState, receiver: mpsc::Receiver<ClipboardRequest>) {
+    while let Ok(first) = receiver.recv() {
+        // Take everything that queued up meanwhile so identical reads can share one backend call
+        let mut batch = vec![first];
+        batch.extend(receiver.try_iter());
+
+        let mut text: Option<Result<String, String>> = None;
+        let mut image: Option<Result<Arc<ClipboardImage>, String>> = None;
+
+        for request in batch {
+            match request {
+                ClipboardRequest::ReadText(reply) => {
+                    let result = text.get_or_insert_with(|| backend.read_text()).clone();
+                    let _ = reply.send(result);
+                }
+                ClipboardRequest::ReadImage(reply) => {
+                    let result = image
+                        .get_or_insert_with(|| backend.read_image().map(Arc::new))
+                        .clone();
+                    let _ = reply.send(result);
+                }
+                ClipboardRequest::Run(job) => {
+                    // A panicking job drops its reply, which the caller sees as an error
+                    if catch_unwind(AssertUnwindSafe(|| job(&*backend))).is_err() {
+                        eprintln!("Clipboard job panicked");
+                    }
+
+                    // Jobs may write to the clipboard, so earlier reads can no longer be shared
+                    text = None;
+                    image = None;
+                }
+            }
+        }
+    }
+}
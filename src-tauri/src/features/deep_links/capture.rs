@@ -0,0 +1,102 @@
+//! Deep-link capture
+//!
+//! Hooks every URL the OS hands to the app into `handle_deep_link_url`:
+//! links the app was launched with (the plugin's current URLs plus matching
+//! argv entries on Linux and Windows) and links delivered while it runs.
+
+use tauri_plugin_deep_link::DeepLinkExt;
+use super::types::DeepLinkSource;
+use super::utils::handle_deep_link_url;
+
+/// Subscribe to runtime deep links and process the ones the app was launched with
+pub fn init_deep_link_capture(app: &tauri::AppHandle) {
+    let handle = app.clone();
+    app.deep_link().on_open_url(move |event| {
+        for url in event.urls() {
+            process_captured_url(&handle, url.as_str(), DeepLinkSource::Running);
+        }
+    });
+
+    for url in launch_urls(app) {
+        process_captured_url(app, &url, DeepLinkSource::ColdStart);
+    }
+}
+
+/// URLs the app was started with, without duplicates
+fn launch_urls(app: &tauri::AppHandle) -> Vec<String> {
+    let mut urls: Vec<String> = match app.deep_link().get_current() {
+        Ok(current) => current.unwrap_or_default().into_iter().map(|url| url.to_string()).collect(),
+        Err(e) => {
+            eprintln!("Failed to read launch deep links: {}", e);
+            Vec::new()
+        }
+    };
+
+    // The plugin only accepts a lone argument with a scheme from its desktop config,
+    // so scan the whole command line for links to the configured schemes and domains
+    if cfg!(any(target_os = "linux", windows)) {
+        for url in std::env::args().skip(1).filter_map(|arg| deep_link_from_arg(app.config(), &arg)) {
+            if !urls.contains(&url) {
+                urls.push(url);
+            }
+        }
+    }
+
+    urls
+}
+
+/// Return the argument as a URL if it targets a configured scheme or domain
+pub fn deep_link_from_arg(config: &tauri::Config, arg: &str) -> Option<String> {
+    let url = url::Url::parse(arg).ok()?;
+    let (schemes, domains) = configured_targets(config);
+
+    let matches = match url.scheme() {
+        "https" | "http" => url.host_str().is_some_and(|host| domains.iter().any(|domain| domain.eq_ignore_ascii_case(host))),
+        scheme => schemes.iter().any(|configured| configured.eq_ignore_ascii_case(scheme)),
+    };
+    matches.then(|| url.to_string())
+}
+
+/// Schemes and universal-link domains from the `deep-link` plugin config
+pub fn configured_targets(config: &tauri::Config) -> (Vec<String>, Vec<String>) {
+    let config = config.plugins.0.get("deep-link");
+    let strings = |key: &str| -> Vec<String> {
+        config
+            .and_then(|config| config.get(key))
+            .and_then(|values| values.as_array())
+            .map(|values| values.iter().filter_map(|value| value.as_str().map(str::to_string)).collect())
+            .unwrap_or_default()
+    };
+
+    let mut schemes = strings("schemes");
+    // The plugin's own `desktop` section holds one protocol or a list of them
+    if let Some(desktop) = config.and_then(|config| config.get("desktop")) {
+        let protocols = match desktop.as_array() {
+            Some(protocols) => protocols.iter().collect(),
+            None => vec![desktop],
+        };
+        for protocol in protocols {
+            if let Some(values) = protocol.get("schemes").and_then(|values| values.as_array()) {
+                schemes.extend(values.iter().filter_map(|value| value.as_str().map(str::to_string)));
+            }
+        }
+    }
+
+    (schemes, strings("domains"))
+}
+
+/// Run a captured URL through the deep-link handler, logging failures
+/// Returns whether the link was delivered to a window, which is then focused
+pub fn process_captured_url(app: &tauri::AppHandle, url: &str, source: DeepLinkSource) -> bool {
+    match handle_deep_link_url(app, url, source) {
+        Ok(result) if !result.success => {
+            eprintln!("Deep link {} not handled: {}", url, result.message);
+            false
+        }
+        Ok(result) => result.window.is_some(),
+        Err(e) => {
+            eprintln!("Failed to handle deep link {}: {}", url, e);
+            false
+        }
+    }
+}
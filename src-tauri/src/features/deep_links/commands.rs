@@ -1,19 +1,59 @@
-use tauri_plugin_deep_link::DeepLinkExt;
-use super::types::DeepLinkResult;
-use super::utils::handle_deep_link_url;
+use std::collections::BTreeMap;
+use tauri::{Manager, State};
+use super::capture::configured_targets;
+use super::delivery::DeepLinkDeliveryState;
+use super::history::DeepLinkHistoryState;
+use super::policy::DeepLinkPolicyState;
+use super::registration::{protocol_status, register_scheme, registered_schemes, unregister_scheme};
+use super::router::DeepLinkRouterState;
+use super::signing::DeepLinkSigner;
+use super::types::{BuildDeepLinkOptions, DeepLinkHistoryPage, DeepLinkHistoryQuery, DeepLinkResult, DeepLinkRoute, DeepLinkRouteMatch, DeepLinkSource, ProtocolRegistration};
+use super::utils::{handle_deep_link_url, route_deep_link_url};
 
 /// Register a protocol for deep linking
 #[tauri::command]
-pub async fn register_protocol(app: tauri::AppHandle, protocol: String) -> Result<(), String> {
-    app.deep_link()
-        .register(&protocol)
-        .map_err(|e| format!("Failed to register protocol '{}': {}", protocol, e))
+pub async fn register_protocol(app: tauri::AppHandle, policy: State<'_, DeepLinkPolicyState>, protocol: String) -> Result<ProtocolRegistration, String> {
+    let registration = register_scheme(&app, &protocol)?;
+
+    // Links for a protocol registered at runtime pass the scheme allowlist
+    let mut policy = policy.lock().map_err(|e| e.to_string())?;
+    policy.allow_scheme(&protocol);
+    Ok(registration)
+}
+
+/// Stop handling a protocol
+#[tauri::command]
+pub async fn unregister_protocol(app: tauri::AppHandle, policy: State<'_, DeepLinkPolicyState>, protocol: String) -> Result<(), String> {
+    unregister_scheme(&app, &protocol)?;
+
+    // Links for a protocol registered at runtime no longer pass the scheme allowlist
+    let mut policy = policy.lock().map_err(|e| e.to_string())?;
+    policy.disallow_scheme(&protocol);
+    Ok(())
+}
+
+/// Whether the app handles a protocol
+#[tauri::command]
+pub async fn is_protocol_registered(app: tauri::AppHandle, protocol: String) -> Result<bool, String> {
+    protocol_status(&app, &protocol, false).map(|status| status.registered)
+}
+
+/// Protocols the app is registered for, from the config, this session and the Linux handler file
+#[tauri::command]
+pub async fn list_registered_protocols(app: tauri::AppHandle) -> Result<Vec<ProtocolRegistration>, String> {
+    Ok(registered_schemes(&app))
+}
+
+/// Check a protocol and, on Linux, restore an incomplete registration of a protocol the app owns
+#[tauri::command]
+pub async fn repair_protocol(app: tauri::AppHandle, protocol: String) -> Result<ProtocolRegistration, String> {
+    protocol_status(&app, &protocol, true)
 }
 
 /// Handle a deep link event with comprehensive processing
 #[tauri::command]
 pub async fn handle_deep_link_event(app: tauri::AppHandle, url: String) -> Result<String, String> {
-    match handle_deep_link_url(&app, &url) {
+    match handle_deep_link_url(&app, &url, DeepLinkSource::Manual) {
         Ok(result) => {
             if result.success {
                 Ok(result.message)
@@ -28,5 +68,136 @@ pub async fn handle_deep_link_event(app: tauri::AppHandle, url: String) -> Resul
 /// Handle a deep link event and return detailed result
 #[tauri::command]
 pub async fn handle_deep_link_detailed(app: tauri::AppHandle, url: String) -> Result<DeepLinkResult, String> {
-    handle_deep_link_url(&app, &url)
-}
\ No newline at end of file
+    handle_deep_link_url(&app, &url, DeepLinkSource::Manual)
+}
+/// List the registered deep-link routes in matching order
+#[tauri::command]
+pub async fn get_deep_link_routes(router: State<'_, DeepLinkRouterState>) -> Result<Vec<DeepLinkRoute>, String> {
+    let router = router.lock().map_err(|e| e.to_string())?;
+    Ok(router.routes())
+}
+
+/// Register a deep-link route, replacing the route with the same name
+#[tauri::command]
+pub async fn add_deep_link_route(router: State<'_, DeepLinkRouterState>, route: DeepLinkRoute) -> Result<(), String> {
+    let mut router = router.lock().map_err(|e| e.to_string())?;
+    router.add_route(route)
+}
+
+#[tauri::command]
+pub async fn remove_deep_link_route(router: State<'_, DeepLinkRouterState>, name: String) -> Result<(), String> {
+    let mut router = router.lock().map_err(|e| e.to_string())?;
+    if router.remove_route(&name) {
+        Ok(())
+    } else {
+        Err(format!("Deep-link route '{}' not found", name))
+    }
+}
+
+/// Match a URL against the route table without handling it
+#[tauri::command]
+pub async fn match_deep_link_route(app: tauri::AppHandle, url: String) -> Result<DeepLinkRouteMatch, String> {
+    route_deep_link_url(&app, &url).map_err(|reasons| format!("No route matched: {}", reasons.join("; ")))
+}
+
+/// Report that the calling window listens for deep links; queued links are emitted to it in order
+#[tauri::command]
+pub async fn deep_links_ready(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    delivery: State<'_, DeepLinkDeliveryState>,
+    policy: State<'_, DeepLinkPolicyState>,
+) -> Result<usize, String> {
+    ensure_app_window(&window, &policy, "receive deep links")?;
+
+    let mut delivery = delivery.lock().map_err(|e| e.to_string())?;
+    delivery.mark_ready(&app, window.label())
+}
+
+/// Acknowledge a delivered deep link so it is not redelivered after a reload
+#[tauri::command]
+pub async fn ack_deep_link(delivery: State<'_, DeepLinkDeliveryState>, id: u64) -> Result<(), String> {
+    let mut delivery = delivery.lock().map_err(|e| e.to_string())?;
+    if delivery.acknowledge(id) {
+        Ok(())
+    } else {
+        Err(format!("No pending deep link with ID {}", id))
+    }
+}
+
+/// Mint a signed link that expires after `ttl_secs`, or the configured default
+#[tauri::command]
+pub async fn sign_deep_link(
+    window: tauri::WebviewWindow,
+    policy: State<'_, DeepLinkPolicyState>,
+    signer: State<'_, DeepLinkSigner>,
+    url: String,
+    ttl_secs: Option<u64>,
+) -> Result<String, String> {
+    ensure_app_window(&window, &policy, "sign deep links")?;
+    signer.sign(&url, ttl_secs)
+}
+
+/// Query the deep-link history, newest first
+#[tauri::command]
+pub async fn get_deep_link_history(history: State<'_, DeepLinkHistoryState>, query: Option<DeepLinkHistoryQuery>) -> Result<DeepLinkHistoryPage, String> {
+    let history = history.lock().map_err(|e| e.to_string())?;
+    Ok(history.query(&query.unwrap_or_default()))
+}
+
+/// Build a percent-encoded link to a route from its parameters, optionally signed and copied
+#[tauri::command]
+pub async fn build_deep_link(
+    window: tauri::WebviewWindow,
+    policy: State<'_, DeepLinkPolicyState>,
+    router: State<'_, DeepLinkRouterState>,
+    signer: State<'_, DeepLinkSigner>,
+    route: String,
+    params: Option<BTreeMap<String, serde_json::Value>>,
+    options: Option<BuildDeepLinkOptions>,
+) -> Result<String, String> {
+    let app = window.app_handle();
+    let options = options.unwrap_or_default();
+    if options.sign {
+        ensure_app_window(&window, &policy, "sign deep links")?;
+    }
+    let (schemes, domains) = configured_targets(app.config());
+    let domain = if options.universal {
+        Some(domains.first().ok_or_else(|| "No universal-link domain is configured".to_string())?.as_str())
+    } else {
+        None
+    };
+    let scheme = schemes.first().map(String::as_str).unwrap_or_default();
+
+    let link = {
+        let router = router.lock().map_err(|e| e.to_string())?;
+        router.build_url(&route, &params.unwrap_or_default(), scheme, domain)?
+    };
+    let link = if options.sign { signer.sign(&link, options.ttl_secs)? } else { link };
+
+    if options.copy_to_clipboard {
+        copy_deep_link(app, link.clone()).await?;
+    }
+    Ok(link)
+}
+
+/// Refuse windows showing external content, which must not receive or mint links
+fn ensure_app_window(window: &tauri::WebviewWindow, policy: &DeepLinkPolicyState, action: &str) -> Result<(), String> {
+    let url = window.url().map_err(|e| format!("Failed to read window URL: {}", e))?;
+    if !policy.lock().map_err(|e| e.to_string())?.is_app_content(&url) {
+        return Err(format!("Window '{}' shows external content and cannot {}", window.label(), action));
+    }
+    Ok(())
+}
+
+#[cfg(feature = "clipboard")]
+async fn copy_deep_link(app: &tauri::AppHandle, link: String) -> Result<(), String> {
+    let worker = app.try_state::<crate::features::clipboard::worker::ClipboardWorker>()
+        .ok_or_else(|| "Clipboard is not initialized".to_string())?;
+    worker.run(move |backend| backend.write_text(&link)).await
+}
+
+#[cfg(not(feature = "clipboard"))]
+async fn copy_deep_link(_app: &tauri::AppHandle, _link: String) -> Result<(), String> {
+    Err("Copying links requires the clipboard feature".to_string())
+}
@@ -0,0 +1,82 @@
+//! Deep-link delivery queue
+//!
+//! Every handled link is queued with an ID for its target window and only
+//! emitted once that window called `deep_links_ready`. A window is no longer ready once its page starts
+//! loading again, so links it has not acknowledged with `ack_deep_link` are
+//! delivered again when the reloaded page calls `deep_links_ready`.
+
+use serde_json::Value;
+use std::collections::{HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use tauri::Emitter;
+
+/// Delivery queue managed as Tauri state
+pub type DeepLinkDeliveryState = Arc<Mutex<DeepLinkDelivery>>;
+
+/// Unacknowledged links kept for redelivery; the oldest are dropped beyond this
+const MAX_PENDING_DEEP_LINKS: usize = 100;
+
+#[derive(Debug)]
+struct PendingDeepLink {
+    id: u64,
+    window: String, // Label of the target window
+    payload: Value,
+}
+
+#[derive(Debug, Default)]
+pub struct DeepLinkDelivery {
+    next_id: u64,
+    pending: VecDeque<PendingDeepLink>,
+    ready_windows: HashSet<String>,
+}
+
+pub fn init_deep_link_delivery() -> DeepLinkDeliveryState {
+    Arc::new(Mutex::new(DeepLinkDelivery::default()))
+}
+
+impl DeepLinkDelivery {
+    /// Queue a link for a window and emit it if the window is ready; returns its delivery ID
+    pub fn enqueue(&mut self, app: &tauri::AppHandle, window: &str, mut payload: Value) -> Result<u64, String> {
+        self.next_id += 1;
+        let id = self.next_id;
+        payload["id"] = Value::from(id);
+
+        // Queue first so a failed emit is still redelivered on the next ready call
+        if self.pending.len() >= MAX_PENDING_DEEP_LINKS && let Some(dropped) = self.pending.pop_front() {
+            eprintln!("Deep-link queue full, dropping unacknowledged link {}", dropped.id);
+        }
+        self.pending.push_back(PendingDeepLink { id, window: window.to_string(), payload: payload.clone() });
+
+        if self.ready_windows.contains(window) {
+            app.emit_to(window, "deep-link-received", &payload)
+                .map_err(|e| format!("Failed to emit deep link event: {}", e))?;
+        }
+
+        Ok(id)
+    }
+
+    /// Mark a window ready and emit its unacknowledged links to it in order
+    pub fn mark_ready(&mut self, app: &tauri::AppHandle, label: &str) -> Result<usize, String> {
+        self.ready_windows.insert(label.to_string());
+
+        let mut delivered = 0;
+        for link in self.pending.iter().filter(|link| link.window == label) {
+            app.emit_to(label, "deep-link-received", &link.payload)
+                .map_err(|e| format!("Failed to emit deep link event: {}", e))?;
+            delivered += 1;
+        }
+        Ok(delivered)
+    }
+
+    /// Stop emitting to a window until it reports ready again
+    pub fn mark_not_ready(&mut self, label: &str) {
+        self.ready_windows.remove(label);
+    }
+
+    /// Drop an acknowledged link; returns false if no pending link has the ID
+    pub fn acknowledge(&mut self, id: u64) -> bool {
+        let before = self.pending.len();
+        self.pending.retain(|link| link.id != id);
+        self.pending.len() != before
+    }
+}
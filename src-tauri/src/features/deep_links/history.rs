@@ -0,0 +1,164 @@
+//! Deep-link history
+//!
+//! Every handled link is appended to `deep_link_history.jsonl` in the app data
+//! directory, one JSON record per line, so links that "did nothing" can be
+//! traced afterwards. The file is trimmed to the most recent records at startup.
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::Manager;
+use super::types::{DeepLinkHistoryEntry, DeepLinkHistoryPage, DeepLinkHistoryQuery, DeepLinkResult, DeepLinkSource};
+
+const DEEP_LINK_HISTORY_FILE: &str = "deep_link_history.jsonl";
+const MAX_HISTORY_ENTRIES: usize = 1000;
+const MAX_RECORDED_URL_LENGTH: usize = 2048;
+
+/// History managed as Tauri state
+pub type DeepLinkHistoryState = Arc<Mutex<DeepLinkHistory>>;
+
+#[derive(Debug, Default)]
+pub struct DeepLinkHistory {
+    path: Option<PathBuf>, // None keeps the history in memory only
+    entries: Vec<DeepLinkHistoryEntry>, // Oldest first
+    next_id: u64,
+}
+
+/// Load the recorded history from the app data directory
+pub fn init_deep_link_history(app: &tauri::AppHandle) -> DeepLinkHistoryState {
+    let path = match app.path().app_data_dir() {
+        Ok(dir) => Some(dir.join(DEEP_LINK_HISTORY_FILE)),
+        Err(e) => {
+            eprintln!("Failed to resolve app data directory, deep-link history will not be saved: {}", e);
+            None
+        }
+    };
+
+    let mut entries = path.as_ref().map(|path| load_history(path)).unwrap_or_default();
+    if entries.len() > MAX_HISTORY_ENTRIES {
+        entries.drain(..entries.len() - MAX_HISTORY_ENTRIES);
+        if let Some(path) = &path
+            && let Err(e) = rewrite_history(path, &entries)
+        {
+            eprintln!("{}", e);
+        }
+    }
+    let next_id = entries.last().map(|entry| entry.id + 1).unwrap_or(1);
+
+    Arc::new(Mutex::new(DeepLinkHistory { path, entries, next_id }))
+}
+
+impl DeepLinkHistory {
+    /// Record how a link was handled
+    pub fn record(&mut self, url: &str, source: DeepLinkSource, result: &Result<DeepLinkResult, String>) {
+        let received_at = SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_millis() as u64).unwrap_or(0);
+        let entry = match result {
+            Ok(result) => DeepLinkHistoryEntry {
+                id: self.next_id,
+                received_at,
+                url: url.chars().take(MAX_RECORDED_URL_LENGTH).collect(),
+                source,
+                outcome: Some(result.outcome),
+                success: result.success,
+                message: result.message.clone(),
+                route: result.route.as_ref().map(|route| route.route.clone()),
+                signature: Some(result.signature),
+            },
+            // The link could not be processed at all
+            Err(e) => DeepLinkHistoryEntry {
+                id: self.next_id,
+                received_at,
+                url: url.chars().take(MAX_RECORDED_URL_LENGTH).collect(),
+                source,
+                outcome: None,
+                success: false,
+                message: e.clone(),
+                route: None,
+                signature: None,
+            },
+        };
+        self.next_id += 1;
+
+        if let Some(path) = &self.path
+            && let Err(e) = append_history(path, &entry)
+        {
+            eprintln!("{}", e);
+        }
+
+        self.entries.push(entry);
+        if self.entries.len() > MAX_HISTORY_ENTRIES {
+            self.entries.remove(0);
+        }
+    }
+
+    /// Matching records, newest first
+    pub fn query(&self, query: &DeepLinkHistoryQuery) -> DeepLinkHistoryPage {
+        let search = query.search.as_ref().map(|search| search.to_lowercase());
+        let matching: Vec<&DeepLinkHistoryEntry> = self.entries.iter()
+            .rev()
+            .filter(|entry| query.source.is_none_or(|source| entry.source == source))
+            .filter(|entry| query.outcome.is_none_or(|outcome| entry.outcome == Some(outcome)))
+            .filter(|entry| query.success.is_none_or(|success| entry.success == success))
+            .filter(|entry| query.since.is_none_or(|since| entry.received_at >= since))
+            .filter(|entry| query.until.is_none_or(|until| entry.received_at <= until))
+            .filter(|entry| search.as_ref().is_none_or(|search| {
+                entry.url.to_lowercase().contains(search) || entry.message.to_lowercase().contains(search)
+            }))
+            .collect();
+
+        DeepLinkHistoryPage {
+            total: matching.len(),
+            entries: matching.into_iter()
+                .skip(query.offset.unwrap_or(0))
+                .take(query.limit.unwrap_or(50))
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+fn load_history(path: &Path) -> Vec<DeepLinkHistoryEntry> {
+    match std::fs::read_to_string(path) {
+        // Skip lines that fail to parse, e.g. one cut off by a crash
+        Ok(contents) => contents.lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect(),
+        // Nothing recorded yet
+        Err(_) => Vec::new(),
+    }
+}
+
+fn append_history(path: &Path, entry: &DeepLinkHistoryEntry) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create deep-link history directory: {}", e))?;
+    }
+    let line = serde_json::to_string(entry)
+        .map_err(|e| format!("Failed to serialize deep-link history entry: {}", e))?;
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open deep-link history: {}", e))?;
+    writeln!(file, "{}", line).map_err(|e| format!("Failed to record deep link: {}", e))
+}
+
+fn rewrite_history(path: &Path, entries: &[DeepLinkHistoryEntry]) -> Result<(), String> {
+    let mut contents = String::new();
+    for entry in entries {
+        let line = serde_json::to_string(entry)
+            .map_err(|e| format!("Failed to serialize deep-link history entry: {}", e))?;
+        contents.push_str(&line);
+        contents.push('\n');
+    }
+
+    // Write next to the target and rename so a crash never leaves a truncated file
+    let tmp_path = path.with_extension("jsonl.tmp");
+    std::fs::write(&tmp_path, contents)
+        .map_err(|e| format!("Failed to trim deep-link history: {}", e))?;
+    std::fs::rename(&tmp_path, path)
+        .map_err(|e| format!("Failed to trim deep-link history: {}", e))
+}
@@ -1,19 +1,43 @@
 //! Deep Links Module
 //! 
 //! This module provides deep link functionality including:
-//! - Protocol registration
+//! - Protocol registration, inspection and repair
+//! - Capture of links delivered at launch and while running
 //! - URL parsing and handling
-//! - Event emission to frontend
+//! - Route table with typed path parameters
+//! - Event emission to the route's target window
+//! - Queued delivery with a ready handshake and acknowledgements
+//! - Single-instance forwarding of launches on Linux
+//! - Scheme, domain, path, length and rate-limit policy
+//! - Signed, expiring links with key rotation
+//! - Persistent history of handled links
 
+#[cfg(feature = "deep-links")]
+pub mod capture;
 #[cfg(feature = "deep-links")]
 pub mod commands;
 #[cfg(feature = "deep-links")]
+pub mod delivery;
+#[cfg(feature = "deep-links")]
+pub mod history;
+#[cfg(feature = "deep-links")]
+pub mod policy;
+#[cfg(feature = "deep-links")]
+pub mod registration;
+#[cfg(feature = "deep-links")]
+pub mod router;
+#[cfg(feature = "deep-links")]
+pub mod signing;
+#[cfg(all(feature = "deep-links", target_os = "linux"))]
+pub mod single_instance;
+#[cfg(feature = "deep-links")]
 pub mod types;
 #[cfg(feature = "deep-links")]
 pub mod utils;
 
 // Re-export all commands for easy access
 #[cfg(feature = "deep-links")]
+#[allow(unused_imports)] // The binary imports the command modules directly
 pub use commands::*;
 // Re-export types and utils when needed
 // #[cfg(feature = "deep-links")]
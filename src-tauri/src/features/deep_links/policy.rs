@@ -0,0 +1,157 @@
+//! Deep-link policy
+//!
+//! Links are accepted only for the schemes and universal-link domains listed in
+//! the `deep-link` plugin config, within the path allowlist and length limits of
+//! its `policy` object, and at most `rate_limit.max_links` per external source
+//! within `rate_limit.window_secs`. Only windows showing the app's own frontend
+//! can subscribe to deep links.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use super::capture::configured_targets;
+use super::types::{DeepLinkData, DeepLinkPolicyConfig, DeepLinkSource};
+
+/// Policy managed as Tauri state
+pub type DeepLinkPolicyState = Arc<Mutex<DeepLinkPolicy>>;
+
+#[derive(Debug)]
+pub struct DeepLinkPolicy {
+    config: DeepLinkPolicyConfig,
+    schemes: Vec<String>,
+    runtime_schemes: Vec<String>, // Schemes allowed by `allow_scheme` that are not in the config
+    domains: Vec<String>,
+    dev_url: Option<url::Url>,
+    recent: HashMap<DeepLinkSource, VecDeque<Instant>>,
+}
+
+/// Create the policy from the `deep-link` plugin config in tauri.conf.json
+pub fn init_deep_link_policy(app: &tauri::AppHandle) -> DeepLinkPolicyState {
+    let (schemes, domains) = configured_targets(app.config());
+
+    let configured = app.config().plugins.0.get("deep-link").and_then(|config| config.get("policy")).cloned();
+    let config = match configured.map(serde_json::from_value::<DeepLinkPolicyConfig>) {
+        Some(Ok(config)) => config,
+        Some(Err(e)) => {
+            eprintln!("Invalid deep-link policy in config, using defaults: {}", e);
+            DeepLinkPolicyConfig::default()
+        }
+        None => DeepLinkPolicyConfig::default(),
+    };
+
+    Arc::new(Mutex::new(DeepLinkPolicy {
+        config,
+        schemes: schemes.iter().map(|scheme| scheme.to_ascii_lowercase()).collect(),
+        runtime_schemes: Vec::new(),
+        domains: domains.iter().map(|domain| domain.to_ascii_lowercase()).collect(),
+        dev_url: app.config().build.dev_url.clone(),
+        recent: HashMap::new(),
+    }))
+}
+
+impl DeepLinkPolicy {
+    /// Allow a scheme registered at runtime
+    pub fn allow_scheme(&mut self, scheme: &str) {
+        let scheme = scheme.to_ascii_lowercase();
+        if !self.schemes.contains(&scheme) {
+            self.schemes.push(scheme.clone());
+            self.runtime_schemes.push(scheme);
+        }
+    }
+
+    /// Stop allowing a scheme that was allowed at runtime; configured schemes stay allowed
+    pub fn disallow_scheme(&mut self, scheme: &str) {
+        let scheme = scheme.to_ascii_lowercase();
+        if let Some(index) = self.runtime_schemes.iter().position(|runtime| *runtime == scheme) {
+            self.runtime_schemes.remove(index);
+            self.schemes.retain(|allowed| *allowed != scheme);
+        }
+    }
+
+    /// Check the raw URL before parsing; counts towards the source's rate limit
+    pub fn check_url(&mut self, url: &str, source: DeepLinkSource) -> Result<(), String> {
+        self.check_rate(source)?;
+
+        if url.len() > self.config.max_url_length {
+            return Err(format!("URL is {} bytes long, the limit is {}", url.len(), self.config.max_url_length));
+        }
+        Ok(())
+    }
+
+    /// Check the parsed link against the allowed targets, paths and parameter lengths
+    pub fn check_link(&self, data: &DeepLinkData) -> Result<(), String> {
+        match data.scheme.as_str() {
+            "https" | "http" => {
+                if !self.domains.contains(&data.host) {
+                    return Err(format!("Host '{}' is not an allowed deep-link domain", data.host));
+                }
+            }
+            scheme => {
+                if !self.schemes.iter().any(|allowed| allowed == scheme) {
+                    return Err(format!("Scheme '{}' is not an allowed deep-link scheme", scheme));
+                }
+            }
+        }
+
+        if !self.config.paths.is_empty() {
+            let segments = link_segments(data);
+            let allowed = self.config.paths.iter().any(|prefix| {
+                let prefix: Vec<&str> = prefix.split('/').filter(|segment| !segment.is_empty()).collect();
+                segments.len() >= prefix.len() && segments.iter().zip(&prefix).all(|(segment, expected)| segment == expected)
+            });
+            if !allowed {
+                return Err(format!("Path '/{}' is not in the deep-link path allowlist", segments.join("/")));
+            }
+        }
+
+        let max = self.config.max_param_length;
+        let too_long = data.path_segments.iter()
+            .chain(data.query_params.iter().flat_map(|param| [&param.key, &param.value]))
+            .chain(data.fragment.iter())
+            .find(|value| value.len() > max);
+        if let Some(value) = too_long {
+            return Err(format!("A parameter is {} bytes long, the limit is {}", value.len(), max));
+        }
+
+        Ok(())
+    }
+
+    /// Whether a window at this URL shows the app's own frontend rather than an external site
+    pub fn is_app_content(&self, url: &url::Url) -> bool {
+        if url.scheme() == "tauri" || url.host_str() == Some("tauri.localhost") {
+            return true;
+        }
+        self.dev_url.as_ref().is_some_and(|dev_url| dev_url.origin() == url.origin())
+    }
+
+    fn check_rate(&mut self, source: DeepLinkSource) -> Result<(), String> {
+        // Links the app's own frontend hands in are not a flood from outside
+        if source == DeepLinkSource::Manual {
+            return Ok(());
+        }
+
+        let limit = &self.config.rate_limit;
+        let window = Duration::from_secs(limit.window_secs);
+        let now = Instant::now();
+
+        let recent = self.recent.entry(source).or_default();
+        while recent.front().is_some_and(|at| now.duration_since(*at) >= window) {
+            recent.pop_front();
+        }
+        if recent.len() >= limit.max_links {
+            return Err(format!("Rate limit of {} links per {}s exceeded", limit.max_links, limit.window_secs));
+        }
+        recent.push_back(now);
+        Ok(())
+    }
+}
+
+/// Path segments the allowlist applies to; for custom schemes the host is the first segment
+fn link_segments(data: &DeepLinkData) -> Vec<String> {
+    let mut segments = Vec::new();
+    if !matches!(data.scheme.as_str(), "https" | "http") && !data.host.is_empty() {
+        segments.push(data.host.clone());
+    }
+    segments.extend(data.path_segments.iter().cloned());
+    segments
+}
@@ -0,0 +1,265 @@
+//! Protocol registration management
+//!
+//! Wraps the deep-link plugin's registration calls with explanatory errors. On
+//! Linux a scheme is registered when the `{exe}-handler.desktop` file in
+//! `$XDG_DATA_HOME/applications` lists `x-scheme-handler/<scheme>` and
+//! `xdg-mime` reports that file as the default handler; when either is missing
+//! for a scheme the app owns, `repair_protocol` registers it again. Checks never
+//! repair on their own, and a scheme unregistered this session is not repaired
+//! until it is registered again.
+
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+use tauri_plugin_deep_link::DeepLinkExt;
+use super::capture::configured_targets;
+use super::types::ProtocolRegistration;
+
+/// Schemes registered through `register_protocol` during this session
+static RUNTIME_PROTOCOLS: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+/// Schemes removed through `unregister_protocol` during this session, configured ones included
+static UNREGISTERED_PROTOCOLS: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+/// Register the app as the handler for a scheme and confirm the registration took effect
+pub fn register_scheme(app: &tauri::AppHandle, scheme: &str) -> Result<ProtocolRegistration, String> {
+    validate_scheme(scheme)?;
+    app.deep_link()
+        .register(scheme)
+        .map_err(|e| explain_error("register", scheme, e))?;
+
+    if let Ok(mut protocols) = RUNTIME_PROTOCOLS.lock() {
+        protocols.insert(scheme.to_string());
+    }
+    if let Ok(mut protocols) = UNREGISTERED_PROTOCOLS.lock() {
+        protocols.remove(scheme);
+    }
+
+    let status = protocol_status(app, scheme, false)?;
+    if !status.registered {
+        return Err(format!(
+            "Registered protocol '{}', but the system still does not use this app for it{}",
+            scheme,
+            status.default_handler.as_ref().map(|handler| format!(" (default handler: {})", handler)).unwrap_or_default()
+        ));
+    }
+    Ok(status)
+}
+
+/// Remove the app as the handler for a scheme
+pub fn unregister_scheme(app: &tauri::AppHandle, scheme: &str) -> Result<(), String> {
+    validate_scheme(scheme)?;
+    app.deep_link()
+        .unregister(scheme)
+        .map_err(|e| explain_error("unregister", scheme, e))?;
+
+    // The plugin only drops the default association, so the handler file keeps advertising the scheme
+    #[cfg(target_os = "linux")]
+    linux::remove_desktop_mime_type(app, scheme)?;
+
+    if let Ok(mut protocols) = RUNTIME_PROTOCOLS.lock() {
+        protocols.remove(scheme);
+    }
+    if let Ok(mut protocols) = UNREGISTERED_PROTOCOLS.lock() {
+        protocols.insert(scheme.to_string());
+    }
+    Ok(())
+}
+
+/// Registration status of every scheme the app knows about that is currently registered
+/// A scheme whose status cannot be checked is listed as not registered, with the error
+pub fn registered_schemes(app: &tauri::AppHandle) -> Vec<ProtocolRegistration> {
+    let mut schemes: BTreeSet<String> = configured_targets(app.config()).0.into_iter().collect();
+    if let Ok(protocols) = RUNTIME_PROTOCOLS.lock() {
+        schemes.extend(protocols.iter().cloned());
+    }
+    #[cfg(target_os = "linux")]
+    schemes.extend(linux::desktop_schemes(app));
+
+    let mut registered = Vec::new();
+    for scheme in schemes {
+        match protocol_status(app, &scheme, false) {
+            Ok(status) if status.registered => registered.push(status),
+            Ok(_) => {}
+            Err(e) => registered.push(ProtocolRegistration {
+                configured: configured_targets(app.config()).0.iter().any(|configured| configured.eq_ignore_ascii_case(&scheme)),
+                scheme,
+                registered: false,
+                desktop_entry: None,
+                default_handler: None,
+                repaired: false,
+                error: Some(e),
+            }),
+        }
+    }
+    registered
+}
+
+/// Check a scheme's registration; with `repair`, re-register a scheme the app owns whose registration is incomplete
+/// A scheme unregistered this session is no longer owned, even when it is configured
+pub fn protocol_status(app: &tauri::AppHandle, scheme: &str, repair: bool) -> Result<ProtocolRegistration, String> {
+    validate_scheme(scheme)?;
+    let configured = configured_targets(app.config()).0.iter().any(|configured| configured.eq_ignore_ascii_case(scheme));
+
+    #[cfg(target_os = "linux")]
+    {
+        let runtime = RUNTIME_PROTOCOLS.lock().map(|protocols| protocols.contains(scheme)).unwrap_or(false);
+        let unregistered = UNREGISTERED_PROTOCOLS.lock().map(|protocols| protocols.contains(scheme)).unwrap_or(false);
+        let mut status = linux::status(app, scheme, configured)?;
+        let owned = !unregistered && (configured || runtime || status.desktop_entry == Some(true));
+        if repair && owned && !status.registered {
+            app.deep_link()
+                .register(scheme)
+                .map_err(|e| explain_error("repair the registration of", scheme, e))?;
+            status = linux::status(app, scheme, configured)?;
+            status.repaired = true;
+        }
+        Ok(status)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        // Registrations outside Linux have no parts that can go missing independently
+        let _ = repair;
+        let registered = match app.deep_link().is_registered(scheme) {
+            Ok(registered) => registered,
+            // Schemes are declared in the app bundle here and cannot be inspected at runtime
+            Err(tauri_plugin_deep_link::Error::UnsupportedPlatform) => configured,
+            Err(e) => return Err(explain_error("check", scheme, e)),
+        };
+        Ok(ProtocolRegistration {
+            scheme: scheme.to_string(),
+            registered,
+            configured,
+            desktop_entry: None,
+            default_handler: None,
+            repaired: false,
+            error: None,
+        })
+    }
+}
+
+/// Schemes are a letter followed by letters, digits, `+`, `-` or `.`
+fn validate_scheme(scheme: &str) -> Result<(), String> {
+    let mut chars = scheme.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid protocol '{}': use the scheme without '://', starting with a letter and containing only letters, digits, '+', '-' or '.'", scheme))
+    }
+}
+
+fn explain_error(action: &str, scheme: &str, error: tauri_plugin_deep_link::Error) -> String {
+    use tauri_plugin_deep_link::Error;
+
+    let reason = match &error {
+        Error::UnsupportedPlatform => {
+            "this platform does not support it at runtime; schemes are declared in the app bundle through the deep-link plugin config".to_string()
+        }
+        Error::Io(e) if e.kind() == std::io::ErrorKind::NotFound && cfg!(target_os = "linux") => {
+            format!("a required file or command is missing ({}); make sure xdg-utils and desktop-file-utils are installed", e)
+        }
+        Error::Io(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
+            format!("permission denied ({}); check that the applications directory and mimeapps.list are writable", e)
+        }
+        other => other.to_string(),
+    };
+    format!("Failed to {} protocol '{}': {}", action, scheme, reason)
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::path::PathBuf;
+    use std::process::Command;
+    use tauri::Manager;
+    use super::super::types::ProtocolRegistration;
+
+    /// Name of the handler file the deep-link plugin generates
+    fn desktop_file_name() -> Result<String, String> {
+        let exe = tauri::utils::platform::current_exe()
+            .map_err(|e| format!("Failed to locate the app executable: {}", e))?;
+        let name = exe.file_name()
+            .ok_or_else(|| "Failed to locate the app executable".to_string())?;
+        Ok(format!("{}-handler.desktop", name.to_string_lossy()))
+    }
+
+    fn desktop_file_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+        let dir = app.path().data_dir()
+            .map_err(|e| format!("Failed to resolve the data directory: {}", e))?;
+        Ok(dir.join("applications").join(desktop_file_name()?))
+    }
+
+    /// Entries of the `MimeType` key in the handler file
+    fn desktop_mime_types(app: &tauri::AppHandle) -> Vec<String> {
+        let Ok(contents) = desktop_file_path(app).and_then(|path| std::fs::read_to_string(path).map_err(|e| e.to_string())) else {
+            return Vec::new();
+        };
+        contents.lines()
+            .find_map(|line| line.trim().strip_prefix("MimeType="))
+            .map(|mimes| mimes.split(';').filter(|mime| !mime.is_empty()).map(str::to_string).collect())
+            .unwrap_or_default()
+    }
+
+    /// Schemes the handler file advertises
+    pub fn desktop_schemes(app: &tauri::AppHandle) -> Vec<String> {
+        desktop_mime_types(app)
+            .iter()
+            .filter_map(|mime| mime.strip_prefix("x-scheme-handler/").map(str::to_string))
+            .collect()
+    }
+
+    pub fn status(app: &tauri::AppHandle, scheme: &str, configured: bool) -> Result<ProtocolRegistration, String> {
+        let mime_type = format!("x-scheme-handler/{}", scheme);
+        let desktop_entry = desktop_mime_types(app).contains(&mime_type);
+
+        let output = Command::new("xdg-mime")
+            .args(["query", "default", &mime_type])
+            .output()
+            .map_err(|e| format!("Failed to run xdg-mime ({}); make sure xdg-utils is installed", e))?;
+        let default_handler = Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+            .filter(|handler| !handler.is_empty());
+
+        Ok(ProtocolRegistration {
+            scheme: scheme.to_string(),
+            registered: desktop_entry && default_handler.as_deref() == Some(desktop_file_name()?.as_str()),
+            configured,
+            desktop_entry: Some(desktop_entry),
+            default_handler,
+            repaired: false,
+            error: None,
+        })
+    }
+
+    /// Drop a scheme from the handler file's `MimeType` key and refresh the desktop database
+    /// The key is removed once it lists nothing
+    pub fn remove_desktop_mime_type(app: &tauri::AppHandle, scheme: &str) -> Result<(), String> {
+        let path = desktop_file_path(app)?;
+        let Ok(contents) = std::fs::read_to_string(&path) else {
+            return Ok(());
+        };
+        let mime_type = format!("x-scheme-handler/{}", scheme);
+
+        let updated: Vec<String> = contents.lines()
+            .filter_map(|line| match line.trim().strip_prefix("MimeType=") {
+                Some(mimes) => {
+                    let kept: Vec<&str> = mimes.split(';').filter(|mime| !mime.is_empty() && *mime != mime_type).collect();
+                    (!kept.is_empty()).then(|| format!("MimeType={};", kept.join(";")))
+                }
+                None => Some(line.to_string()),
+            })
+            .collect();
+
+        std::fs::write(&path, updated.join("\n") + "\n")
+            .map_err(|e| format!("Failed to update {}: {}", path.display(), e))?;
+
+        // Same refresh the plugin runs after writing the file, so the mime cache forgets the scheme
+        if let Some(dir) = path.parent() {
+            Command::new("update-desktop-database")
+                .arg(dir)
+                .status()
+                .map_err(|e| format!("Failed to run update-desktop-database ({}); make sure desktop-file-utils is installed", e))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,560 @@
+//! Deep-link router
+//!
+//! Routes are matched against the host and path of a deep link, so
+//! `tauri-starterkit://project/:id/file/*path` matches
+//! `tauri-starterkit://project/42/file/src/main.rs` with `id = 42` and
+//! `path = "src/main.rs"`. Universal links to one of the configured `domains`
+//! match the same routes by their path alone, so
+//! `https://tauri-app.com/project/42/file/src/main.rs` matches too, unless the
+//! pattern itself names an `http(s)` scheme. Routes are
+//! registered at runtime or loaded from the `routes` array of the `deep-link`
+//! plugin config in tauri.conf.json.
+
+use super::capture::configured_targets;
+use super::types::{DeepLinkRoute, DeepLinkRouteMatch, DeepLinkWindowTemplate, RouteParamSpec, RouteParamType};
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, CONTROLS};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+/// Window that receives links whose route declares no window, and links no route matched
+pub const DEFAULT_DEEP_LINK_WINDOW: &str = "main";
+
+/// Router managed as Tauri state
+pub type DeepLinkRouterState = Arc<Mutex<DeepLinkRouter>>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Param(String),
+    Rest(String),
+}
+
+#[derive(Debug, Clone)]
+struct CompiledRoute {
+    route: DeepLinkRoute,
+    scheme: Option<String>, // None matches any scheme
+    segments: Vec<Segment>,
+}
+
+/// Ordered route table; the first route that matches and validates wins
+#[derive(Debug, Default)]
+pub struct DeepLinkRouter {
+    routes: Vec<CompiledRoute>,
+    domains: Vec<String>, // Universal-link domains whose links match app-scheme routes
+}
+
+/// Create the router with the routes configured in tauri.conf.json
+pub fn init_deep_link_router(app: &tauri::AppHandle) -> DeepLinkRouterState {
+    let mut router = DeepLinkRouter {
+        domains: configured_targets(app.config()).1,
+        ..Default::default()
+    };
+
+    let configured = app.config().plugins.0.get("deep-link").and_then(|config| config.get("routes")).cloned();
+    if let Some(routes) = configured {
+        match serde_json::from_value::<Vec<DeepLinkRoute>>(routes) {
+            Ok(routes) => {
+                for route in routes {
+                    let name = route.name.clone();
+                    if let Err(e) = router.add_route(route) {
+                        eprintln!("Skipping deep-link route '{}': {}", name, e);
+                    }
+                }
+            }
+            Err(e) => eprintln!("Invalid deep-link routes in config: {}", e),
+        }
+    }
+
+    Arc::new(Mutex::new(router))
+}
+
+impl DeepLinkRouter {
+    /// Add a route, replacing any route with the same name in place
+    pub fn add_route(&mut self, route: DeepLinkRoute) -> Result<(), String> {
+        let compiled = compile_route(route)?;
+        match self.routes.iter_mut().find(|existing| existing.route.name == compiled.route.name) {
+            Some(existing) => *existing = compiled,
+            None => self.routes.push(compiled),
+        }
+        Ok(())
+    }
+
+    /// Remove a route by name; returns false if there was none
+    pub fn remove_route(&mut self, name: &str) -> bool {
+        let before = self.routes.len();
+        self.routes.retain(|compiled| compiled.route.name != name);
+        self.routes.len() != before
+    }
+
+    pub fn routes(&self) -> Vec<DeepLinkRoute> {
+        self.routes.iter().map(|compiled| compiled.route.clone()).collect()
+    }
+
+    /// Window template of a route, if it declares one
+    pub fn window_template(&self, name: &str) -> Option<DeepLinkWindowTemplate> {
+        self.routes.iter()
+            .find(|compiled| compiled.route.name == name)
+            .and_then(|compiled| compiled.route.window_template.clone())
+    }
+
+    /// Match a URL against the route table
+    /// Returns the match, or the reasons why every candidate route was rejected
+    pub fn match_url(&self, url: &url::Url) -> Result<DeepLinkRouteMatch, Vec<String>> {
+        let web_link = is_web_scheme(url.scheme());
+        let universal_domain = url.host_str().is_some_and(|host| self.domains.iter().any(|domain| domain.eq_ignore_ascii_case(host)));
+        let segments = url_segments(url);
+        let query: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+        let mut reasons = Vec::new();
+
+        for compiled in &self.routes {
+            let web_route = compiled.scheme.as_deref().is_some_and(is_web_scheme);
+            // Universal links skip the scheme check and their domain for app-scheme routes,
+            // as long as the domain is one the app handles
+            let universal = web_link && !web_route;
+            if universal && !universal_domain {
+                continue;
+            }
+            if let Some(scheme) = &compiled.scheme
+                && !universal
+                && !scheme.eq_ignore_ascii_case(url.scheme())
+            {
+                continue;
+            }
+            let candidate = if universal { &segments[1.min(segments.len())..] } else { &segments[..] };
+            let Some(raw_params) = match_segments(&compiled.segments, candidate) else {
+                continue;
+            };
+
+            match resolve_params(&compiled.route, raw_params, &query) {
+                Ok(params) => {
+                    return Ok(DeepLinkRouteMatch {
+                        route: compiled.route.name.clone(),
+                        pattern: compiled.route.pattern.clone(),
+                        params,
+                        window: compiled.route.window.clone().unwrap_or_else(|| DEFAULT_DEEP_LINK_WINDOW.to_string()),
+                    });
+                }
+                Err(e) => reasons.push(format!("Route '{}': {}", compiled.route.name, e)),
+            }
+        }
+
+        if reasons.is_empty() {
+            reasons.push(format!("No route pattern matches '{}'", url));
+        }
+        if web_link && !universal_domain {
+            reasons.push(format!("'{}' is not a configured universal-link domain", url.host_str().unwrap_or_default()));
+        }
+        Err(reasons)
+    }
+
+    /// Build a link to a route from its parameters, the inverse of `match_url`
+    /// `scheme` is used for routes without a scheme; with a `domain` a universal link is built instead
+    pub fn build_url(
+        &self,
+        name: &str,
+        params: &BTreeMap<String, serde_json::Value>,
+        scheme: &str,
+        domain: Option<&str>,
+    ) -> Result<String, String> {
+        let compiled = self.routes.iter()
+            .find(|compiled| compiled.route.name == name)
+            .ok_or_else(|| format!("Deep-link route '{}' not found", name))?;
+
+        let raw = |name: &str| -> Option<String> {
+            match params.get(name)? {
+                serde_json::Value::String(value) => Some(value.clone()),
+                serde_json::Value::Null => None,
+                value => Some(value.to_string()),
+            }
+        };
+        let default_spec = RouteParamSpec::default();
+
+        let mut path = Vec::new();
+        let mut path_params = Vec::new();
+        for segment in &compiled.segments {
+            match segment {
+                Segment::Literal(literal) => path.push(encode_segment(literal)),
+                Segment::Param(param) | Segment::Rest(param) => {
+                    let spec = compiled.route.params.get(param).unwrap_or(&default_spec);
+                    let value = raw(param)
+                        .or_else(|| spec.default.clone())
+                        .ok_or_else(|| format!("Missing required parameter '{}'", param))?;
+                    convert_param(param, &value, spec)?;
+
+                    if matches!(segment, Segment::Rest(_)) {
+                        // Slashes separate segments of the rest, everything else is encoded
+                        path.extend(value.split('/').filter(|part| !part.is_empty()).map(encode_segment));
+                    } else if value.is_empty() {
+                        return Err(format!("Parameter '{}' cannot be empty", param));
+                    } else {
+                        path.push(encode_segment(&value));
+                    }
+                    path_params.push(param.as_str());
+                }
+            }
+        }
+
+        // Declared parameters that are not in the path travel in the query
+        let mut query = Vec::new();
+        for key in params.keys() {
+            if path_params.contains(&key.as_str()) {
+                continue;
+            }
+            let Some(spec) = compiled.route.params.get(key) else {
+                return Err(format!("Route '{}' has no parameter '{}'", name, key));
+            };
+            if let Some(value) = raw(key) {
+                convert_param(key, &value, spec)?;
+                query.push((key.clone(), value));
+            }
+        }
+
+        let web_route = compiled.scheme.as_deref().is_some_and(is_web_scheme);
+        let base = match (&compiled.scheme, domain) {
+            // As in `match_url`, the path of a universal link starts with the app-scheme host
+            (_, Some(domain)) if !web_route => format!("https://{}/", domain),
+            (Some(scheme), _) => format!("{}://", scheme),
+            (None, _) => format!("{}://", scheme),
+        };
+
+        let mut url = url::Url::parse(&format!("{}{}", base, path.join("/")))
+            .map_err(|e| format!("Failed to build deep link: {}", e))?;
+        if !query.is_empty() {
+            url.query_pairs_mut().extend_pairs(query);
+        }
+
+        // The link must come back to this route, not to an earlier one
+        match self.match_url(&url) {
+            Ok(matched) if matched.route == name => Ok(url.to_string()),
+            Ok(matched) => Err(format!("Built link {} would be handled by route '{}'", url, matched.route)),
+            Err(reasons) => Err(format!("Built link {} does not match its route: {}", url, reasons.join("; "))),
+        }
+    }
+}
+
+fn compile_route(route: DeepLinkRoute) -> Result<CompiledRoute, String> {
+    if route.name.trim().is_empty() {
+        return Err("Route name cannot be empty".to_string());
+    }
+
+    let (scheme, rest) = match route.pattern.split_once("://") {
+        Some((scheme, rest)) => (Some(scheme.to_string()).filter(|scheme| scheme != "*"), rest),
+        None => (None, route.pattern.as_str()),
+    };
+
+    let parts: Vec<&str> = rest.split('/').filter(|part| !part.is_empty()).collect();
+    let mut segments = Vec::with_capacity(parts.len());
+    for (index, part) in parts.iter().enumerate() {
+        let segment = if let Some(name) = part.strip_prefix(':') {
+            Segment::Param(name.to_string())
+        } else if let Some(name) = part.strip_prefix('*') {
+            if index != parts.len() - 1 {
+                return Err(format!("Wildcard '*{}' must be the last segment of '{}'", name, route.pattern));
+            }
+            Segment::Rest(name.to_string())
+        } else {
+            Segment::Literal(part.to_string())
+        };
+
+        if let Segment::Param(name) | Segment::Rest(name) = &segment {
+            if name.is_empty() {
+                return Err(format!("Unnamed parameter in '{}'", route.pattern));
+            }
+            if segments.iter().any(|existing| matches!(existing, Segment::Param(other) | Segment::Rest(other) if other == name)) {
+                return Err(format!("Parameter '{}' appears twice in '{}'", name, route.pattern));
+            }
+        }
+        segments.push(segment);
+    }
+
+    if let Some(label) = &route.window
+        && !label.chars().all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '/' | ':'))
+    {
+        return Err(format!("Invalid window label '{}' in route '{}'", label, route.name));
+    }
+    if let Some(template) = &route.window_template {
+        if route.window.is_none() {
+            return Err(format!("Route '{}' has a window template but no window label", route.name));
+        }
+        if template.url.contains("://") {
+            return Err(format!("Window template of route '{}' must load an app route, not '{}'", route.name, template.url));
+        }
+    }
+
+    // Defaults must be valid values themselves
+    for (name, spec) in &route.params {
+        if let Some(default) = &spec.default {
+            convert_param(name, default, spec)?;
+        }
+    }
+
+    Ok(CompiledRoute { route, scheme, segments })
+}
+
+/// Characters escaped in a path segment: controls, delimiters and anything that
+/// would otherwise be read as part of the path, query or fragment
+const PATH_SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ').add(b'"').add(b'#').add(b'%').add(b'/').add(b'<').add(b'>')
+    .add(b':').add(b'?').add(b'@').add(b'[').add(b'\\').add(b']').add(b'^').add(b'`').add(b'{').add(b'|').add(b'}');
+
+fn encode_segment(segment: &str) -> String {
+    utf8_percent_encode(segment, PATH_SEGMENT).to_string()
+}
+
+fn is_web_scheme(scheme: &str) -> bool {
+    scheme.eq_ignore_ascii_case("https") || scheme.eq_ignore_ascii_case("http")
+}
+
+/// Host and decoded path segments of a URL; the host is the first segment
+fn url_segments(url: &url::Url) -> Vec<String> {
+    let mut segments = Vec::new();
+    if let Some(host) = url.host_str()
+        && !host.is_empty()
+    {
+        segments.push(host.to_string());
+    }
+    segments.extend(
+        url.path()
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(|segment| percent_decode_str(segment).decode_utf8_lossy().into_owned()),
+    );
+    segments
+}
+
+fn match_segments(pattern: &[Segment], segments: &[String]) -> Option<BTreeMap<String, String>> {
+    let mut params = BTreeMap::new();
+    let mut index = 0;
+
+    for segment in pattern {
+        match segment {
+            Segment::Literal(literal) => {
+                if segments.get(index)? != literal {
+                    return None;
+                }
+                index += 1;
+            }
+            Segment::Param(name) => {
+                params.insert(name.clone(), segments.get(index)?.clone());
+                index += 1;
+            }
+            Segment::Rest(name) => {
+                // An empty rest counts as missing, so a default can apply
+                if index < segments.len() {
+                    params.insert(name.clone(), segments[index..].join("/"));
+                }
+                index = segments.len();
+            }
+        }
+    }
+
+    (index == segments.len()).then_some(params)
+}
+
+/// Convert and validate path parameters, then fill in query parameters and defaults
+fn resolve_params(
+    route: &DeepLinkRoute,
+    raw_params: BTreeMap<String, String>,
+    query: &[(String, String)],
+) -> Result<BTreeMap<String, serde_json::Value>, String> {
+    let default_spec = RouteParamSpec::default();
+    let mut params = BTreeMap::new();
+
+    for (name, raw) in &raw_params {
+        let spec = route.params.get(name).unwrap_or(&default_spec);
+        params.insert(name.clone(), convert_param(name, raw, spec)?);
+    }
+
+    for (name, spec) in &route.params {
+        if params.contains_key(name) {
+            continue;
+        }
+        let raw = query.iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+            .or(spec.default.as_deref())
+            .ok_or_else(|| format!("Missing required parameter '{}'", name))?;
+        params.insert(name.clone(), convert_param(name, raw, spec)?);
+    }
+
+    Ok(params)
+}
+
+/// Convert a raw parameter to its declared type and check its constraints
+fn convert_param(name: &str, raw: &str, spec: &RouteParamSpec) -> Result<serde_json::Value, String> {
+    if let Some(allowed) = &spec.one_of
+        && !allowed.iter().any(|value| value == raw)
+    {
+        return Err(format!("Parameter '{}' must be one of {}", name, allowed.join(", ")));
+    }
+
+    let check_range = |value: f64, what: &str| -> Result<(), String> {
+        if let Some(min) = spec.min
+            && value < min
+        {
+            return Err(format!("Parameter '{}' {} must be at least {}", name, what, min));
+        }
+        if let Some(max) = spec.max
+            && value > max
+        {
+            return Err(format!("Parameter '{}' {} must be at most {}", name, what, max));
+        }
+        Ok(())
+    };
+
+    match spec.param_type {
+        RouteParamType::String => {
+            check_range(raw.chars().count() as f64, "length")?;
+            Ok(serde_json::Value::String(raw.to_string()))
+        }
+        RouteParamType::Integer => {
+            let value: i64 = raw.parse()
+                .map_err(|_| format!("Parameter '{}' must be an integer, got '{}'", name, raw))?;
+            check_range(value as f64, "value")?;
+            Ok(serde_json::Value::from(value))
+        }
+        RouteParamType::Number => {
+            let value: f64 = raw.parse()
+                .ok()
+                .filter(|value: &f64| value.is_finite())
+                .ok_or_else(|| format!("Parameter '{}' must be a number, got '{}'", name, raw))?;
+            check_range(value, "value")?;
+            Ok(serde_json::Value::from(value))
+        }
+        RouteParamType::Boolean => match raw.to_ascii_lowercase().as_str() {
+            "true" | "1" | "yes" | "on" => Ok(serde_json::Value::Bool(true)),
+            "false" | "0" | "no" | "off" => Ok(serde_json::Value::Bool(false)),
+            _ => Err(format!("Parameter '{}' must be a boolean, got '{}'", name, raw)),
+        },
+        RouteParamType::Uuid => {
+            if is_uuid(raw) {
+                Ok(serde_json::Value::String(raw.to_ascii_lowercase()))
+            } else {
+                Err(format!("Parameter '{}' must be a UUID, got '{}'", name, raw))
+            }
+        }
+    }
+}
+
+/// Canonical 8-4-4-4-12 hexadecimal UUID
+fn is_uuid(value: &str) -> bool {
+    let groups: Vec<&str> = value.split('-').collect();
+    groups.len() == 5
+        && groups.iter().zip([8, 4, 4, 4, 12]).all(|(group, len)| {
+            group.len() == len && group.chars().all(|c| c.is_ascii_hexdigit())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn route(value: serde_json::Value) -> DeepLinkRoute {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn router() -> DeepLinkRouter {
+        let mut router = DeepLinkRouter {
+            routes: Vec::new(),
+            domains: vec!["tauri-app.com".to_string()],
+        };
+        router.add_route(route(json!({
+            "name": "project-file",
+            "pattern": "tauri-starterkit://project/:id/file/*path",
+            "params": { "id": { "type": "integer", "min": 1 } }
+        }))).unwrap();
+        router
+    }
+
+    fn match_str(router: &DeepLinkRouter, url: &str) -> Result<DeepLinkRouteMatch, Vec<String>> {
+        router.match_url(&url::Url::parse(url).unwrap())
+    }
+
+    fn project_params() -> BTreeMap<String, serde_json::Value> {
+        BTreeMap::from([("id".to_string(), json!(42)), ("path".to_string(), json!("src/main.rs"))])
+    }
+
+    #[test]
+    fn builds_app_scheme_links_that_match_their_route() {
+        let router = router();
+        let url = router.build_url("project-file", &project_params(), "tauri-starterkit", None).unwrap();
+        assert_eq!(url, "tauri-starterkit://project/42/file/src/main.rs");
+
+        let matched = match_str(&router, &url).unwrap();
+        assert_eq!(matched.route, "project-file");
+        assert_eq!(matched.params, project_params());
+    }
+
+    #[test]
+    fn builds_universal_links_for_app_scheme_routes() {
+        let router = router();
+        let url = router.build_url("project-file", &project_params(), "tauri-starterkit", Some("tauri-app.com")).unwrap();
+        assert_eq!(url, "https://tauri-app.com/project/42/file/src/main.rs");
+        assert_eq!(match_str(&router, &url).unwrap().params, project_params());
+    }
+
+    #[test]
+    fn universal_links_round_trip_through_build_url() {
+        let router = router();
+        let matched = match_str(&router, "https://tauri-app.com/project/7/file/docs/readme.md").unwrap();
+        let url = router.build_url(&matched.route, &matched.params, "tauri-starterkit", Some("tauri-app.com")).unwrap();
+        assert_eq!(url, "https://tauri-app.com/project/7/file/docs/readme.md");
+    }
+
+    #[test]
+    fn matches_named_and_rest_segments() {
+        let matched = match_str(&router(), "tauri-starterkit://project/42/file/docs/guide/intro.md").unwrap();
+        assert_eq!(matched.route, "project-file");
+        assert_eq!(matched.params["id"], json!(42));
+        assert_eq!(matched.params["path"], json!("docs/guide/intro.md"));
+    }
+
+    #[test]
+    fn empty_rest_segment_falls_back_to_its_default() {
+        let mut router = router();
+        router.add_route(route(json!({
+            "name": "docs",
+            "pattern": "tauri-starterkit://docs/*page",
+            "params": { "page": { "default": "index" } }
+        }))).unwrap();
+
+        assert_eq!(match_str(&router, "tauri-starterkit://docs").unwrap().params["page"], json!("index"));
+        assert_eq!(match_str(&router, "tauri-starterkit://docs/api/router").unwrap().params["page"], json!("api/router"));
+    }
+
+    #[test]
+    fn checks_typed_params_against_their_bounds() {
+        let mut router = router();
+        router.add_route(route(json!({
+            "name": "zoom",
+            "pattern": "tauri-starterkit://zoom/:level",
+            "params": { "level": { "type": "number", "min": 0.5, "max": 4 } }
+        }))).unwrap();
+
+        assert_eq!(match_str(&router, "tauri-starterkit://zoom/1.5").unwrap().params["level"], json!(1.5));
+        assert!(match_str(&router, "tauri-starterkit://zoom/0.25").is_err());
+        assert!(match_str(&router, "tauri-starterkit://zoom/8").is_err());
+        assert!(match_str(&router, "tauri-starterkit://zoom/large").is_err());
+
+        // The integer `id` of the project route has a minimum of 1
+        assert!(match_str(&router, "tauri-starterkit://project/0/file/readme.md").is_err());
+        assert!(match_str(&router, "tauri-starterkit://project/1.5/file/readme.md").is_err());
+    }
+
+    #[test]
+    fn strips_the_host_of_universal_links() {
+        let matched = match_str(&router(), "https://tauri-app.com/project/3/file/readme.md").unwrap();
+        assert_eq!(matched.route, "project-file");
+        assert_eq!(matched.params["id"], json!(3));
+
+        // Without stripping, the domain would be taken for the `project` host segment
+        assert!(match_str(&router(), "https://tauri-app.com/3/file/readme.md").is_err());
+    }
+
+    #[test]
+    fn ignores_universal_links_on_unknown_domains() {
+        let reasons = match_str(&router(), "https://example.com/project/3/file/readme.md").unwrap_err();
+        assert!(reasons.iter().any(|reason| reason.contains("not a configured universal-link domain")));
+    }
+}
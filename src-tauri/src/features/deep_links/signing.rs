@@ -0,0 +1,246 @@
+//! Signed, expiring deep links
+//!
+//! A signed link carries `exp` (Unix seconds), `kid` (key ID) and `sig`, an
+//! HMAC-SHA256 over the link without its `sig` parameter and fragment, encoded
+//! as unpadded URL-safe base64. Keys come from the `signing` object of the
+//! `deep-link` plugin config; after a rotation the previous keys stay listed so
+//! links that were already sent keep verifying until they expire.
+
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::time::{SystemTime, UNIX_EPOCH};
+use super::types::{DeepLinkSigningConfig, SignatureStatus};
+
+type HmacSha256 = Hmac<Sha256>;
+
+const SIGNATURE_PARAMS: [&str; 3] = ["exp", "kid", "sig"];
+
+/// Key set and protected routes, managed as Tauri state
+pub struct DeepLinkSigner {
+    keys: Vec<(String, Vec<u8>)>,
+    active_kid: Option<String>,
+    protected_routes: Vec<String>,
+    default_ttl_secs: u64,
+    max_ttl_secs: u64,
+}
+
+/// Load the key set from the `deep-link` plugin config, resolving secrets from the environment
+pub fn init_deep_link_signer(app: &tauri::AppHandle) -> DeepLinkSigner {
+    let configured = app.config().plugins.0.get("deep-link").and_then(|config| config.get("signing")).cloned();
+    let config = match configured.map(serde_json::from_value::<DeepLinkSigningConfig>) {
+        Some(Ok(config)) => Some(config),
+        Some(Err(e)) => {
+            eprintln!("Invalid deep-link signing config, signed links are disabled: {}", e);
+            None
+        }
+        None => None,
+    };
+    let Some(config) = config else {
+        return DeepLinkSigner { keys: Vec::new(), active_kid: None, protected_routes: Vec::new(), default_ttl_secs: 0, max_ttl_secs: 0 };
+    };
+
+    let mut keys = Vec::new();
+    for key in config.keys {
+        let secret = key.secret_env.as_ref()
+            .and_then(|var| std::env::var(var).ok())
+            .or(key.secret);
+        match secret.filter(|secret| !secret.is_empty()) {
+            Some(secret) => keys.push((key.kid, secret.into_bytes())),
+            None => eprintln!("Skipping deep-link signing key '{}': no secret available", key.kid),
+        }
+    }
+
+    let active_kid = config.active_kid
+        .filter(|kid| keys.iter().any(|(known, _)| known == kid))
+        .or_else(|| keys.last().map(|(kid, _)| kid.clone()));
+
+    DeepLinkSigner {
+        keys,
+        active_kid,
+        protected_routes: config.protected_routes,
+        default_ttl_secs: config.default_ttl_secs,
+        max_ttl_secs: config.max_ttl_secs,
+    }
+}
+
+impl DeepLinkSigner {
+    /// Sign a link with the active key; replaces any existing signature parameters
+    /// Lifetimes above the configured maximum are refused
+    pub fn sign(&self, url: &str, ttl_secs: Option<u64>) -> Result<String, String> {
+        let kid = self.active_kid.as_ref()
+            .ok_or_else(|| "No deep-link signing key is configured".to_string())?;
+        let mut url = url::Url::parse(url).map_err(|e| format!("Invalid URL format: {}", e))?;
+
+        let ttl_secs = ttl_secs.unwrap_or(self.default_ttl_secs);
+        if ttl_secs > self.max_ttl_secs {
+            return Err(format!("Link lifetime of {}s exceeds the maximum of {}s", ttl_secs, self.max_ttl_secs));
+        }
+        let exp = unix_now().checked_add(ttl_secs)
+            .ok_or_else(|| format!("Link lifetime of {}s is out of range", ttl_secs))?;
+        let pairs: Vec<(String, String)> = url.query_pairs()
+            .filter(|(key, _)| !SIGNATURE_PARAMS.contains(&key.as_ref()))
+            .map(|(key, value)| (key.into_owned(), value.into_owned()))
+            .collect();
+        url.query_pairs_mut()
+            .clear()
+            .extend_pairs(pairs)
+            .append_pair("exp", &exp.to_string())
+            .append_pair("kid", kid);
+
+        let signature = self.mac(kid, &signing_input(&url))?.finalize().into_bytes();
+        url.query_pairs_mut().append_pair("sig", &URL_SAFE_NO_PAD.encode(signature));
+        Ok(url.to_string())
+    }
+
+    /// Check the signature and expiry of a link
+    pub fn verify(&self, url: &url::Url) -> SignatureStatus {
+        let param = |name: &str| url.query_pairs().find(|(key, _)| key == name).map(|(_, value)| value.into_owned());
+
+        let Some(signature) = param("sig") else {
+            return SignatureStatus::Unsigned;
+        };
+        let Some(kid) = param("kid") else {
+            return SignatureStatus::Invalid;
+        };
+        let Ok(mac) = self.mac(&kid, &signing_input(url)) else {
+            return SignatureStatus::UnknownKey;
+        };
+        let Ok(signature) = URL_SAFE_NO_PAD.decode(signature) else {
+            return SignatureStatus::Invalid;
+        };
+        if mac.verify_slice(&signature).is_err() {
+            return SignatureStatus::Invalid;
+        }
+
+        match param("exp").and_then(|exp| exp.parse::<u64>().ok()) {
+            Some(exp) if exp > unix_now() => SignatureStatus::Valid,
+            Some(_) => SignatureStatus::Expired,
+            None => SignatureStatus::Invalid,
+        }
+    }
+
+    /// Whether a route only accepts validly signed links
+    pub fn is_protected(&self, route: &str) -> bool {
+        self.protected_routes.iter().any(|protected| protected == route)
+    }
+
+    fn mac(&self, kid: &str, input: &str) -> Result<HmacSha256, String> {
+        let (_, secret) = self.keys.iter()
+            .find(|(known, _)| known == kid)
+            .ok_or_else(|| format!("Unknown deep-link signing key '{}'", kid))?;
+        let mut mac = HmacSha256::new_from_slice(secret).map_err(|e| e.to_string())?;
+        mac.update(input.as_bytes());
+        Ok(mac)
+    }
+}
+
+/// The link as signed: without its `sig` parameter and fragment
+fn signing_input(url: &url::Url) -> String {
+    let mut url = url.clone();
+    url.set_fragment(None);
+
+    let pairs: Vec<(String, String)> = url.query_pairs()
+        .filter(|(key, _)| key != "sig")
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+    if pairs.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+    url.to_string()
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs()).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signer(keys: &[&str], active_kid: &str) -> DeepLinkSigner {
+        DeepLinkSigner {
+            keys: keys.iter().map(|kid| (kid.to_string(), format!("secret-{}", kid).into_bytes())).collect(),
+            active_kid: Some(active_kid.to_string()),
+            protected_routes: vec!["billing".to_string()],
+            default_ttl_secs: 3600,
+            max_ttl_secs: 86400,
+        }
+    }
+
+    fn verify_str(signer: &DeepLinkSigner, url: &str) -> SignatureStatus {
+        signer.verify(&url::Url::parse(url).unwrap())
+    }
+
+    #[test]
+    fn signed_links_verify() {
+        let signer = signer(&["primary"], "primary");
+        let link = signer.sign("tauri-starterkit://billing/invoice?id=7", None).unwrap();
+        assert_eq!(verify_str(&signer, &link), SignatureStatus::Valid);
+
+        // The fragment is not signed
+        assert_eq!(verify_str(&signer, &format!("{}#details", link)), SignatureStatus::Valid);
+        assert_eq!(verify_str(&signer, "tauri-starterkit://billing/invoice?id=7"), SignatureStatus::Unsigned);
+    }
+
+    #[test]
+    fn tampered_links_are_invalid() {
+        let signer = signer(&["primary"], "primary");
+        let link = signer.sign("tauri-starterkit://billing/invoice?id=7", None).unwrap();
+        assert_eq!(verify_str(&signer, &link.replace("id=7", "id=8")), SignatureStatus::Invalid);
+    }
+
+    #[test]
+    fn resigning_replaces_the_previous_signature() {
+        let signer = signer(&["primary"], "primary");
+        let link = signer.sign("tauri-starterkit://billing/invoice?id=7", None).unwrap();
+        let resigned = signer.sign(&link, Some(60)).unwrap();
+        assert_eq!(resigned.matches("sig=").count(), 1);
+        assert_eq!(verify_str(&signer, &resigned), SignatureStatus::Valid);
+    }
+
+    #[test]
+    fn links_expire() {
+        let signer = signer(&["primary"], "primary");
+        let link = signer.sign("tauri-starterkit://billing/invoice", Some(0)).unwrap();
+        assert_eq!(verify_str(&signer, &link), SignatureStatus::Expired);
+    }
+
+    #[test]
+    fn rejects_lifetimes_above_the_maximum() {
+        let signer = signer(&["primary"], "primary");
+        assert!(signer.sign("tauri-starterkit://billing/invoice", Some(86401)).is_err());
+        assert!(signer.sign("tauri-starterkit://billing/invoice", Some(u64::MAX)).is_err());
+    }
+
+    #[test]
+    fn lifetimes_that_overflow_the_expiry_are_errors() {
+        let signer = DeepLinkSigner { max_ttl_secs: u64::MAX, ..signer(&["primary"], "primary") };
+        assert!(signer.sign("tauri-starterkit://billing/invoice", Some(u64::MAX)).is_err());
+    }
+
+    #[test]
+    fn rotated_keys_keep_verifying_until_removed() {
+        let before = signer(&["2024"], "2024");
+        let old_link = before.sign("tauri-starterkit://billing/invoice", None).unwrap();
+
+        let rotated = signer(&["2024", "2025"], "2025");
+        let new_link = rotated.sign("tauri-starterkit://billing/invoice", None).unwrap();
+        assert!(new_link.contains("kid=2025"));
+        assert_eq!(verify_str(&rotated, &old_link), SignatureStatus::Valid);
+        assert_eq!(verify_str(&rotated, &new_link), SignatureStatus::Valid);
+
+        let retired = signer(&["2025"], "2025");
+        assert_eq!(verify_str(&retired, &old_link), SignatureStatus::UnknownKey);
+        assert_eq!(verify_str(&retired, &new_link), SignatureStatus::Valid);
+    }
+
+    #[test]
+    fn protects_only_listed_routes() {
+        let signer = signer(&["primary"], "primary");
+        assert!(signer.is_protected("billing"));
+        assert!(!signer.is_protected("project-file"));
+    }
+}
@@ -0,0 +1,149 @@
+//! Single-instance enforcement on Linux
+//!
+//! Opening a deep link on Linux launches the executable again. The first
+//! process holds a per-user lock file and listens on a Unix socket next to it;
+//! a later process that cannot take the lock forwards its launch arguments over
+//! the socket and exits, and the first process handles the forwarded links.
+
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::Emitter;
+use super::capture::{deep_link_from_arg, process_captured_url};
+use super::types::{DeepLinkSource, ForwardedLaunch};
+use super::utils::focus_main_window;
+
+/// How long a connecting process gets to send its launch before the listener moves on
+const FORWARD_READ_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Result of trying to become the single running instance
+pub enum SingleInstance {
+    /// This process holds the lock and accepts launches forwarded by later ones
+    Primary(SingleInstanceListener),
+    /// Another instance is running and received this launch; exit now
+    Forwarded,
+}
+
+/// Lock and socket owned by the primary instance
+pub struct SingleInstanceListener {
+    lock: File,
+    listener: UnixListener,
+}
+
+/// Take the per-user instance lock, or forward this launch to the instance holding it
+pub fn acquire_single_instance(config: &tauri::Config) -> Result<SingleInstance, String> {
+    let (lock_path, socket_path) = instance_paths(&config.identifier)?;
+
+    let lock = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)
+        .map_err(|e| format!("Failed to open instance lock {}: {}", lock_path.display(), e))?;
+
+    if lock.try_lock().is_err() {
+        forward_launch(config, &socket_path)?;
+        return Ok(SingleInstance::Forwarded);
+    }
+
+    // Holding the lock means any socket left behind belongs to an instance that has exited
+    let _ = std::fs::remove_file(&socket_path);
+    let listener = UnixListener::bind(&socket_path)
+        .map_err(|e| format!("Failed to listen on {}: {}", socket_path.display(), e))?;
+
+    Ok(SingleInstance::Primary(SingleInstanceListener { lock, listener }))
+}
+
+/// Handle launches forwarded by later instances on a background thread
+pub fn start_single_instance_listener(app: &tauri::AppHandle, instance: SingleInstanceListener) {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        // The lock is released when the process exits
+        let _lock = instance.lock;
+        for stream in instance.listener.incoming() {
+            let launch = stream
+                .map_err(|e| e.to_string())
+                .and_then(read_forwarded_launch);
+            match launch {
+                Ok(launch) => handle_forwarded_launch(&app, launch),
+                Err(e) => eprintln!("Failed to read forwarded launch: {}", e),
+            }
+        }
+    });
+}
+
+fn read_forwarded_launch(mut stream: UnixStream) -> Result<ForwardedLaunch, String> {
+    // A client that connects and never closes must not stall later launches
+    stream.set_read_timeout(Some(FORWARD_READ_TIMEOUT)).map_err(|e| e.to_string())?;
+    let mut message = String::new();
+    stream.read_to_string(&mut message).map_err(|e| e.to_string())?;
+    serde_json::from_str(&message).map_err(|e| e.to_string())
+}
+
+fn handle_forwarded_launch(app: &tauri::AppHandle, launch: ForwardedLaunch) {
+    let mut delivered = false;
+    for url in &launch.urls {
+        delivered |= process_captured_url(app, url, DeepLinkSource::SecondInstance);
+    }
+
+    // A delivered link already focused its route's window
+    if !delivered && let Err(e) = focus_main_window(app) {
+        eprintln!("Failed to focus main window for forwarded launch: {}", e);
+    }
+    if let Err(e) = app.emit("second-instance", &launch) {
+        eprintln!("Failed to emit second-instance event: {}", e);
+    }
+}
+
+/// Send this process's arguments, deep links and working directory to the primary instance
+fn forward_launch(config: &tauri::Config, socket_path: &Path) -> Result<(), String> {
+    let args: Vec<String> = std::env::args().collect();
+    let launch = ForwardedLaunch {
+        urls: args.iter().skip(1).filter_map(|arg| deep_link_from_arg(config, arg)).collect(),
+        cwd: std::env::current_dir().map(|dir| dir.to_string_lossy().into_owned()).unwrap_or_default(),
+        args,
+    };
+    let message = serde_json::to_string(&launch)
+        .map_err(|e| format!("Failed to serialize launch arguments: {}", e))?;
+
+    let mut stream = UnixStream::connect(socket_path)
+        .map_err(|e| format!("Failed to reach the running instance at {}: {}", socket_path.display(), e))?;
+    stream.write_all(message.as_bytes())
+        .map_err(|e| format!("Failed to forward launch arguments: {}", e))
+}
+
+/// Lock and socket paths, private to the current user
+fn instance_paths(identifier: &str) -> Result<(PathBuf, PathBuf), String> {
+    let dir = match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None => private_temp_dir(identifier)?,
+    };
+    Ok((dir.join(format!("{}.lock", identifier)), dir.join(format!("{}.sock", identifier))))
+}
+
+/// Per-user directory in the temp directory that only the current user can access
+fn private_temp_dir(identifier: &str) -> Result<PathBuf, String> {
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+
+    // procfs entries of a process are owned by its user
+    let uid = std::fs::metadata("/proc/self")
+        .map_err(|e| format!("Failed to determine the current user: {}", e))?
+        .uid();
+    let dir = std::env::temp_dir().join(format!("{}-{}", identifier, uid));
+
+    match std::fs::DirBuilder::new().mode(0o700).create(&dir) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+        Err(e) => return Err(format!("Failed to create {}: {}", dir.display(), e)),
+    }
+
+    // The path may have been created by someone else first
+    let metadata = std::fs::symlink_metadata(&dir)
+        .map_err(|e| format!("Failed to inspect {}: {}", dir.display(), e))?;
+    if !metadata.is_dir() || metadata.uid() != uid || metadata.permissions().mode() & 0o077 != 0 {
+        return Err(format!("Refusing to use {}: it is not a private directory of the current user", dir.display()));
+    }
+    Ok(dir)
+}
@@ -1,12 +1,208 @@
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DeepLinkData {
+    pub url: String, // Original URL exactly as received
+    pub normalized_url: String, // URL as serialized after parsing
+    pub scheme: String,
+    pub host: String, // Lowercase ASCII host, internationalized domains in punycode
+    pub host_unicode: String, // Host with punycode labels decoded for display
+    pub port: Option<u16>,
+    pub username: String, // Percent-decoded userinfo user, empty if absent
+    pub password: Option<String>, // Percent-decoded userinfo password
+    pub path: String, // Raw path
+    pub path_segments: Vec<String>, // Percent-decoded, empty segments dropped
+    pub query: String, // Raw query string without the leading '?'
+    pub query_params: Vec<QueryParam>, // Decoded query parameters in order, repeated keys kept
+    pub fragment: Option<String>, // Percent-decoded fragment without the leading '#'
+}
+
+/// Deep-link policy, the `policy` object of the `deep-link` plugin config
+/// Allowed schemes and universal-link domains come from its `schemes` and `domains`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeepLinkPolicyConfig {
+    #[serde(default)]
+    pub paths: Vec<String>, // Allowed path prefixes such as `/project`, the host counts as the first segment for custom schemes; empty allows all
+    #[serde(default = "default_max_url_length")]
+    pub max_url_length: usize,
+    #[serde(default = "default_max_param_length")]
+    pub max_param_length: usize, // Applies to decoded path segments, query keys and values, and the fragment
+    #[serde(default)]
+    pub rate_limit: DeepLinkRateLimit,
+}
+
+/// Links accepted per source within a sliding window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeepLinkRateLimit {
+    pub max_links: usize,
+    pub window_secs: u64,
+}
+
+impl Default for DeepLinkPolicyConfig {
+    fn default() -> Self {
+        Self {
+            paths: Vec::new(),
+            max_url_length: default_max_url_length(),
+            max_param_length: default_max_param_length(),
+            rate_limit: DeepLinkRateLimit::default(),
+        }
+    }
+}
+
+impl Default for DeepLinkRateLimit {
+    fn default() -> Self {
+        Self { max_links: 10, window_secs: 10 }
+    }
+}
+
+fn default_max_url_length() -> usize {
+    2048
+}
+
+fn default_max_param_length() -> usize {
+    512
+}
+
+/// Signed-link settings, the `signing` object of the `deep-link` plugin config
+/// Only deserialized, so secrets never reach the frontend
+#[derive(Clone, Deserialize)]
+pub struct DeepLinkSigningConfig {
+    #[serde(default)]
+    pub keys: Vec<DeepLinkSigningKey>,
+    #[serde(default)]
+    pub active_kid: Option<String>, // Key new links are signed with, defaults to the last key
+    #[serde(default)]
+    pub protected_routes: Vec<String>, // Route names that only accept validly signed links
+    #[serde(default = "default_signed_link_ttl")]
+    pub default_ttl_secs: u64,
+    #[serde(default = "default_max_signed_link_ttl")]
+    pub max_ttl_secs: u64, // Longest lifetime a link may be signed for
+}
+
+/// HMAC key; keep old keys listed after rotating so links already sent stay valid
+#[derive(Clone, Deserialize)]
+pub struct DeepLinkSigningKey {
+    pub kid: String,
+    #[serde(default)]
+    pub secret: Option<String>, // Inline secret, for development only
+    #[serde(default)]
+    pub secret_env: Option<String>, // Environment variable holding the secret
+}
+
+fn default_signed_link_ttl() -> u64 {
+    3600
+}
+
+fn default_max_signed_link_ttl() -> u64 {
+    30 * 24 * 3600
+}
+
+/// Single query parameter; a key may appear several times
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct QueryParam {
+    pub key: String,
+    pub value: String,
+}
+
+/// How a deep link was handled
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DeepLinkOutcome {
+    /// Delivered and matched a route
+    Routed,
+    /// Delivered, but no route matched
+    NoRouteMatched,
+    /// The URL could not be parsed
+    InvalidUrl,
+    /// Parsed, but delivering it to the frontend failed
+    Failed,
+    /// Refused by the deep-link policy
+    Rejected,
+}
+
+/// Signature check of a deep link
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureStatus {
+    /// No `sig` parameter
+    Unsigned,
+    /// Signed with a known key and not expired
+    Valid,
+    /// Correctly signed, but past its `exp` timestamp
+    Expired,
+    /// Signature does not match, or `exp` is missing
+    Invalid,
+    /// Signed with a key ID that is not in the key set
+    UnknownKey,
+}
+
+/// Where a deep link came from
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum DeepLinkSource {
+    /// The app was launched with the link
+    ColdStart,
+    /// The OS delivered the link while the app was running
+    Running,
+    /// Forwarded by a second launch of the app
+    SecondInstance,
+    /// Handed to the backend by the frontend through a command
+    Manual,
+}
+
+/// Recorded handling of one deep link
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeepLinkHistoryEntry {
+    pub id: u64,
+    pub received_at: u64, // Unix time in milliseconds
     pub url: String,
+    pub source: DeepLinkSource,
+    pub outcome: Option<DeepLinkOutcome>, // None when the link could not be processed at all
+    pub success: bool,
+    pub message: String,
+    pub route: Option<String>, // Name of the matched route
+    pub signature: Option<SignatureStatus>,
+}
+
+/// Filters and pagination for the deep-link history; unset filters match everything
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeepLinkHistoryQuery {
+    pub source: Option<DeepLinkSource>,
+    pub outcome: Option<DeepLinkOutcome>,
+    pub success: Option<bool>,
+    pub since: Option<u64>, // Unix time in milliseconds, inclusive
+    pub until: Option<u64>,
+    pub search: Option<String>, // Case-insensitive substring of the URL or message
+    pub offset: Option<usize>,
+    pub limit: Option<usize>, // Defaults to 50
+}
+
+/// Page of history entries, newest first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeepLinkHistoryPage {
+    pub entries: Vec<DeepLinkHistoryEntry>,
+    pub total: usize, // Matching entries across all pages
+}
+
+/// Registration of the app as handler for a URL scheme
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProtocolRegistration {
     pub scheme: String,
-    pub host: String,
-    pub path: String,
-    pub query: String,
+    pub registered: bool,
+    pub configured: bool, // Listed in the deep-link plugin config
+    pub desktop_entry: Option<bool>, // Linux: the handler .desktop file lists the scheme
+    pub default_handler: Option<String>, // Linux: .desktop file xdg-mime reports as default handler
+    pub repaired: bool, // An incomplete registration was restored during the check
+    pub error: Option<String>, // Why the status could not be checked; the scheme is then reported as not registered
+}
+
+/// Launch forwarded from a second process to the running instance
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForwardedLaunch {
+    pub args: Vec<String>,
+    pub urls: Vec<String>, // Arguments that are deep links for the configured schemes or domains
+    pub cwd: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -14,4 +210,85 @@ pub struct DeepLinkResult {
     pub success: bool,
     pub message: String,
     pub data: Option<DeepLinkData>,
-}
\ No newline at end of file
+    pub outcome: DeepLinkOutcome,
+    pub source: DeepLinkSource,
+    pub signature: SignatureStatus,
+    pub route: Option<DeepLinkRouteMatch>, // Set when a route matched
+    pub delivery_id: Option<u64>, // ID the frontend acknowledges the queued event with
+    pub window: Option<String>, // Label of the window the link was delivered to
+}
+
+/// Type a route parameter is converted to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum RouteParamType {
+    #[default]
+    String,
+    Integer,
+    Number,
+    Boolean,
+    Uuid,
+}
+
+/// Type, default and validation of a route parameter
+/// Parameters not named in the pattern are read from the query string
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct RouteParamSpec {
+    #[serde(default, rename = "type")]
+    pub param_type: RouteParamType,
+    #[serde(default)]
+    pub default: Option<String>, // Used when the parameter is missing; without it the parameter is required
+    #[serde(default)]
+    pub min: Option<f64>, // Minimum value for numbers, minimum length for strings
+    #[serde(default)]
+    pub max: Option<f64>, // Maximum value for numbers, maximum length for strings
+    #[serde(default)]
+    pub one_of: Option<Vec<String>>, // Allowed raw values
+}
+
+/// Deep-link route such as `tauri-starterkit://project/:id/file/*path`
+/// `:name` matches one path segment, `*name` the rest of the path
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeepLinkRoute {
+    pub name: String,
+    pub pattern: String,
+    #[serde(default)]
+    pub params: HashMap<String, RouteParamSpec>,
+    #[serde(default)]
+    pub window: Option<String>, // Label of the window that receives the link, defaults to "main"
+    #[serde(default)]
+    pub window_template: Option<DeepLinkWindowTemplate>, // Creates the target window when it does not exist
+}
+
+/// Options for building a link to a route
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BuildDeepLinkOptions {
+    #[serde(default)]
+    pub universal: bool, // Build an https link on the first configured domain instead of the app scheme
+    #[serde(default)]
+    pub sign: bool,
+    #[serde(default)]
+    pub ttl_secs: Option<u64>, // Lifetime of a signed link, defaults to the signing config
+    #[serde(default)]
+    pub copy_to_clipboard: bool,
+}
+
+/// Window created for a route whose target window is not open
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeepLinkWindowTemplate {
+    pub title: String,
+    pub url: String, // App route such as `/projects`; external sites cannot receive deep links
+    pub width: Option<f64>,
+    pub height: Option<f64>,
+    pub resizable: Option<bool>,
+    pub center: Option<bool>,
+}
+
+/// Route matched by a deep link and its typed parameters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeepLinkRouteMatch {
+    pub route: String, // Route name
+    pub pattern: String,
+    pub params: BTreeMap<String, serde_json::Value>,
+    pub window: String, // Label of the window the link is delivered to
+}
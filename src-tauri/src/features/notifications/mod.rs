@@ -14,6 +14,7 @@ pub mod utils;
 
 // Re-export all commands for easy access
 #[cfg(feature = "notifications")]
+#[allow(unused_imports)] // The binary imports the command modules directly
 pub use commands::*;
 // Re-export types and utils when needed
 // #[cfg(feature = "notifications")]
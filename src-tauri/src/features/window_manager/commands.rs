@@ -39,16 +39,12 @@ pub async fn create_window(
         .title(&options.title);
 
     // Apply optional settings
-    if let Some(width) = options.width {
-        if let Some(height) = options.height {
-            builder = builder.inner_size(width as f64, height as f64);
-        }
+    if let Some(width) = options.width && let Some(height) = options.height {
+        builder = builder.inner_size(width as f64, height as f64);
     }
 
-    if let Some(x) = options.x {
-        if let Some(y) = options.y {
-            builder = builder.position(x as f64, y as f64);
-        }
+    if let Some(x) = options.x && let Some(y) = options.y {
+        builder = builder.position(x as f64, y as f64);
     }
 
     if let Some(resizable) = options.resizable {
@@ -58,6 +58,7 @@ pub struct WindowState {
 }
 
 /// Event types for window communication
+#[allow(dead_code)] // Public API of the library, unused by the binary
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum WindowEventType {
     WindowCreated,
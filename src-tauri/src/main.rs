@@ -24,14 +24,33 @@ use tray::*;
 use menu::*;
 #[cfg(feature = "clipboard")]
 use features::clipboard::commands::*;
+#[cfg(feature = "clipboard")]
+use features::clipboard::{backend::init_clipboard_backend, store::init_clipboard_store, worker::ClipboardWorker};
 #[cfg(feature = "deep-links")]
 use features::deep_links::commands::*;
+#[cfg(feature = "deep-links")]
+use features::deep_links::{capture::init_deep_link_capture, delivery::{init_deep_link_delivery, DeepLinkDeliveryState}, history::init_deep_link_history, policy::init_deep_link_policy, router::init_deep_link_router, signing::init_deep_link_signer};
+#[cfg(all(feature = "deep-links", target_os = "linux"))]
+use features::deep_links::single_instance::{acquire_single_instance, start_single_instance_listener, SingleInstance};
 #[cfg(feature = "window-manager")]
 use features::window_manager::commands::*;
 #[cfg(feature = "window-manager")]
 use features::window_manager::utils::*;
 
 fn main() {
+    let context = tauri::generate_context!();
+
+    // Opening a deep link on Linux starts a new process, which hands its launch to the running one
+    #[cfg(all(feature = "deep-links", target_os = "linux"))]
+    let single_instance = match acquire_single_instance(context.config()) {
+        Ok(SingleInstance::Primary(listener)) => Some(listener),
+        Ok(SingleInstance::Forwarded) => return,
+        Err(e) => {
+            eprintln!("Single-instance mode unavailable: {}", e);
+            None
+        }
+    };
+
     let builder = tauri::Builder::default()
         .plugin(tauri_plugin_shell::init());
 
@@ -87,6 +106,8 @@ fn main() {
             #[cfg(feature = "clipboard")]
             get_clipboard_history,
             #[cfg(feature = "clipboard")]
+            get_clipboard_source_app,
+            #[cfg(feature = "clipboard")]
             get_clipboard_formats,
             #[cfg(feature = "clipboard")]
             clear_clipboard,
@@ -96,12 +117,112 @@ fn main() {
             monitor_clipboard_changes,
             #[cfg(feature = "clipboard")]
             clear_clipboard_history,
+            #[cfg(feature = "clipboard")]
+            resize_clipboard_image,
+            #[cfg(feature = "clipboard")]
+            crop_clipboard_image,
+            #[cfg(feature = "clipboard")]
+            rotate_clipboard_image,
+            #[cfg(feature = "clipboard")]
+            grayscale_clipboard_image,
+            #[cfg(feature = "clipboard")]
+            reencode_clipboard_image,
+            #[cfg(feature = "clipboard")]
+            generate_qr_code,
+            #[cfg(feature = "clipboard")]
+            decode_clipboard_barcodes,
+            #[cfg(feature = "clipboard")]
+            delete_clipboard_history_item,
+            #[cfg(feature = "clipboard")]
+            update_clipboard_history_item,
+            #[cfg(feature = "clipboard")]
+            annotate_clipboard_history_item,
+            #[cfg(feature = "clipboard")]
+            move_clipboard_history_item,
+            #[cfg(feature = "clipboard")]
+            merge_clipboard_history_items,
+            #[cfg(feature = "clipboard")]
+            pin_clipboard_history_item,
+            #[cfg(feature = "clipboard")]
+            get_clipboard_history_generation,
+            #[cfg(feature = "clipboard")]
+            get_clipboard_history_stats,
+            #[cfg(feature = "clipboard")]
+            copy_secret,
+            #[cfg(feature = "clipboard")]
+            get_clipboard_collections,
+            #[cfg(feature = "clipboard")]
+            create_clipboard_collection,
+            #[cfg(feature = "clipboard")]
+            rename_clipboard_collection,
+            #[cfg(feature = "clipboard")]
+            delete_clipboard_collection,
+            #[cfg(feature = "clipboard")]
+            move_clipboard_collection,
+            #[cfg(feature = "clipboard")]
+            add_to_clipboard_collection,
+            #[cfg(feature = "clipboard")]
+            remove_from_clipboard_collection,
+            #[cfg(feature = "clipboard")]
+            move_clipboard_collection_item,
+            #[cfg(feature = "clipboard")]
+            start_collect_mode,
+            #[cfg(feature = "clipboard")]
+            stop_collect_mode,
+            #[cfg(feature = "clipboard")]
+            get_paste_stack,
+            #[cfg(feature = "clipboard")]
+            copy_paste_stack,
+            #[cfg(feature = "clipboard")]
+            pop_paste_stack,
+            #[cfg(feature = "clipboard")]
+            get_clipboard_rules,
+            #[cfg(feature = "clipboard")]
+            set_clipboard_rules,
+            #[cfg(feature = "clipboard")]
+            save_clipboard_rule,
+            #[cfg(feature = "clipboard")]
+            delete_clipboard_rule,
+            #[cfg(feature = "clipboard")]
+            test_clipboard_rules,
+            #[cfg(feature = "clipboard")]
+            evaluate_clipboard_calculation,
+            #[cfg(feature = "clipboard")]
+            copy_calculation_result,
+            #[cfg(feature = "clipboard")]
+            diff_clipboard_items,
             #[cfg(feature = "deep-links")]
             register_protocol,
             #[cfg(feature = "deep-links")]
+            unregister_protocol,
+            #[cfg(feature = "deep-links")]
+            is_protocol_registered,
+            #[cfg(feature = "deep-links")]
+            list_registered_protocols,
+            #[cfg(feature = "deep-links")]
+            repair_protocol,
+            #[cfg(feature = "deep-links")]
             handle_deep_link_event,
             #[cfg(feature = "deep-links")]
             handle_deep_link_detailed,
+            #[cfg(feature = "deep-links")]
+            get_deep_link_routes,
+            #[cfg(feature = "deep-links")]
+            add_deep_link_route,
+            #[cfg(feature = "deep-links")]
+            remove_deep_link_route,
+            #[cfg(feature = "deep-links")]
+            match_deep_link_route,
+            #[cfg(feature = "deep-links")]
+            deep_links_ready,
+            #[cfg(feature = "deep-links")]
+            ack_deep_link,
+            #[cfg(feature = "deep-links")]
+            sign_deep_link,
+            #[cfg(feature = "deep-links")]
+            get_deep_link_history,
+            #[cfg(feature = "deep-links")]
+            build_deep_link,
             #[cfg(feature = "window-manager")]
             create_window,
             #[cfg(feature = "window-manager")]
@@ -123,6 +244,18 @@ fn main() {
     #[cfg(feature = "window-manager")]
     let app_builder = app_builder.manage(init_window_state());
 
+    // A window reloading has lost its deep-link listeners until it reports ready again
+    #[cfg(feature = "deep-links")]
+    let app_builder = app_builder
+        .manage(init_deep_link_delivery())
+        .on_page_load(|webview, payload| {
+            if payload.event() == tauri::webview::PageLoadEvent::Started
+                && let Ok(mut delivery) = webview.state::<DeepLinkDeliveryState>().lock()
+            {
+                delivery.mark_not_ready(webview.label());
+            }
+        });
+
     app_builder
         .setup(|app| {
             // Create application menu
@@ -130,6 +263,29 @@ fn main() {
             app.set_menu(app_menu)?;
             app.on_menu_event(handle_menu_event);
 
+            // Clipboard commands queue their clipboard access on a worker thread owning the backend
+            #[cfg(feature = "clipboard")]
+            app.manage(ClipboardWorker::spawn(init_clipboard_backend(app.handle())));
+            #[cfg(feature = "clipboard")]
+            init_clipboard_store(app.handle());
+
+            // Deep links are checked against the policy and matched against the routes configured in tauri.conf.json
+            #[cfg(feature = "deep-links")]
+            app.manage(init_deep_link_router(app.handle()));
+            #[cfg(feature = "deep-links")]
+            app.manage(init_deep_link_policy(app.handle()));
+            #[cfg(feature = "deep-links")]
+            app.manage(init_deep_link_signer(app.handle()));
+            #[cfg(feature = "deep-links")]
+            app.manage(init_deep_link_history(app.handle()));
+            // Route launch URLs and links opened while running without waiting for the frontend
+            #[cfg(feature = "deep-links")]
+            init_deep_link_capture(app.handle());
+            #[cfg(all(feature = "deep-links", target_os = "linux"))]
+            if let Some(listener) = single_instance {
+                start_single_instance_listener(app.handle(), listener);
+            }
+
             #[cfg(feature = "system-tray")]
             {
                 // Create system tray
@@ -165,6 +321,6 @@ fn main() {
 
             Ok(())
         })
-        .run(tauri::generate_context!())
+        .run(context)
         .expect("error while running tauri application");
 }
\ No newline at end of file
@@ -15,6 +15,22 @@ interface ClipboardFormat {
   raw_data?: string // Base64 encoded raw data for advanced formats
 }
 
+interface CalculationResult {
+  expression: string
+  value: number
+  unit?: string // Target unit of a conversion
+  display: string // Value as it is copied to the clipboard, e.g. "182.88 cm"
+}
+
+interface ClipboardSourceApp {
+  name?: string // WM_CLASS class name, or the process name if unset
+  instance?: string // WM_CLASS instance name
+  pid?: number
+  executable?: string // Process name of pid
+  window_id?: number // Selection owner window
+  platform: string // Display protocol used for detection ("x11", "xwayland")
+}
+
 interface ClipboardHistoryItem {
   id: string
   timestamp: string
@@ -29,11 +45,21 @@ interface ClipboardHistoryItem {
   image_data?: string // Base64 encoded image data
   image_format?: string // Image format (png, jpg, etc.)
   image_dimensions?: [number, number] // Width, Height
+  image_hash?: string // Hash of the raw pixels, used to spot re-copied images
   // File formats
   file_paths?: string[] // File paths for file drops
   file_list?: string // File list as text
   // Advanced formats
   custom_formats?: Record<string, string> // Custom format data
+  // User annotations
+  label?: string // User-given name for the item
+  note?: string // Free-form note attached to the item
+  pinned: boolean // Pinned items are never evicted from history
+  source_app?: ClipboardSourceApp // Application that owned the clipboard at capture time
+  // Set by capture rules
+  hide_preview?: boolean // The content is withheld from the frontend
+  expires_at?: number // Unix timestamp in seconds after which the item is dropped from history
+  calculation?: CalculationResult // Result when the content is an arithmetic or unit expression
 }
 
 interface ClipboardEntry {
@@ -663,8 +689,18 @@ export default function ClipboardDemo() {
                       </span>
                     </div>
                   </div>
+
+                  {(item.pinned || item.label) && (
+                    <div className="text-sm font-medium text-black dark:text-white mb-1">
+                      {item.pinned && '📌 '}{item.label}
+                    </div>
+                  )}
                   
-                  {item.content_type === 'Image' && item.image_data ? (
+                  {item.hide_preview ? (
+                    <div className="text-sm italic text-gray-500 dark:text-gray-400 mb-2">
+                      Preview hidden by a capture rule
+                    </div>
+                  ) : item.content_type === 'Image' && item.image_data ? (
                     <div className="mb-2 flex items-center justify-center h-20 bg-gray-100 dark:bg-gray-800 rounded border overflow-hidden relative">
                       <Image
                         src={`data:image/${item.image_format || 'png'};base64,${item.image_data}`}
@@ -679,14 +715,22 @@ export default function ClipboardDemo() {
                       {item.primary_content.length > 120 && '...'}
                     </div>
                   )}
+
+                  {item.calculation && (
+                    <div className="text-sm font-mono text-gray-700 dark:text-gray-300 mb-2">
+                      = {item.calculation.display}
+                    </div>
+                  )}
                   
                   <div className="flex items-center justify-between">
                     <span className="text-xs text-gray-500 dark:text-gray-400">
                       {item.formats.length} format{item.formats.length !== 1 ? 's' : ''}
+                      {item.source_app?.name && ` • ${item.source_app.name}`}
+                      {item.expires_at && ` • expires ${formatTimestamp(new Date(item.expires_at * 1000).toISOString())}`}
                     </span>
                     <div className="flex items-center gap-1 opacity-0 group-hover:opacity-100 transition-opacity">
                       <Eye size={12} className="text-gray-400" />
-                      <Copy
+                      {!item.hide_preview && <Copy
                         size={12}
                         className="text-gray-400 hover:text-black dark:hover:text-white"
                         onClick={(e) => {
@@ -700,7 +744,7 @@ export default function ClipboardDemo() {
                             copyToClipboard(item.primary_content)
                           }
                         }}
-                      />
+                      />}
                     </div>
                   </div>
                 </div>
@@ -735,6 +779,60 @@ export default function ClipboardDemo() {
                   <label className="text-sm font-medium text-gray-700 dark:text-gray-300">Timestamp:</label>
                   <p className="text-black dark:text-white">{formatTimestamp(selectedHistoryItem.timestamp)}</p>
                 </div>
+
+                {selectedHistoryItem.calculation && (
+                  <div>
+                    <label className="text-sm font-medium text-gray-700 dark:text-gray-300">Result:</label>
+                    <div className="flex items-center justify-between">
+                      <p className="text-black dark:text-white font-mono">= {selectedHistoryItem.calculation.display}</p>
+                      <button
+                        onClick={() => {
+                          invoke('copy_calculation_result', { itemId: selectedHistoryItem.id })
+                            .then(() => showMessage('Result copied to clipboard!', 'success'))
+                            .catch((err) => showMessage(`Failed to copy result: ${err}`, 'error'))
+                        }}
+                        className="text-sm text-gray-500 hover:text-black dark:hover:text-white"
+                      >
+                        Copy Result
+                      </button>
+                    </div>
+                  </div>
+                )}
+
+                {selectedHistoryItem.source_app && (
+                  <div>
+                    <label className="text-sm font-medium text-gray-700 dark:text-gray-300">Source App:</label>
+                    <p className="text-black dark:text-white">
+                      {selectedHistoryItem.source_app.name || selectedHistoryItem.source_app.executable || 'Unknown'}
+                      {selectedHistoryItem.source_app.pid && ` (PID ${selectedHistoryItem.source_app.pid})`}
+                    </p>
+                  </div>
+                )}
+
+                {selectedHistoryItem.expires_at && (
+                  <div>
+                    <label className="text-sm font-medium text-gray-700 dark:text-gray-300">Expires:</label>
+                    <p className="text-black dark:text-white">
+                      {formatTimestamp(new Date(selectedHistoryItem.expires_at * 1000).toISOString())}
+                    </p>
+                  </div>
+                )}
+
+                {selectedHistoryItem.label && (
+                  <div>
+                    <label className="text-sm font-medium text-gray-700 dark:text-gray-300">Label:</label>
+                    <p className="text-black dark:text-white">
+                      {selectedHistoryItem.pinned && '📌 '}{selectedHistoryItem.label}
+                    </p>
+                  </div>
+                )}
+
+                {selectedHistoryItem.note && (
+                  <div>
+                    <label className="text-sm font-medium text-gray-700 dark:text-gray-300">Note:</label>
+                    <p className="text-black dark:text-white whitespace-pre-wrap">{selectedHistoryItem.note}</p>
+                  </div>
+                )}
                 
                 <div>
                   <label className="text-sm font-medium text-gray-700 dark:text-gray-300">Available Formats:</label>
@@ -753,7 +851,11 @@ export default function ClipboardDemo() {
                 
                 <div>
                   <label className="text-sm font-medium text-gray-700 dark:text-gray-300">Content:</label>
-                  {selectedHistoryItem.content_type === 'Image' && selectedHistoryItem.image_data ? (
+                  {selectedHistoryItem.hide_preview ? (
+                    <p className="mt-2 text-sm italic text-gray-500 dark:text-gray-400">
+                      Preview hidden by a capture rule
+                    </p>
+                  ) : selectedHistoryItem.content_type === 'Image' && selectedHistoryItem.image_data ? (
                     <div className="mt-2 border border-gray-300 dark:border-gray-700 rounded-md bg-gray-50 dark:bg-gray-800 p-4 flex items-center justify-center relative h-64">
                       <Image
                         src={`data:image/${selectedHistoryItem.image_format || 'png'};base64,${selectedHistoryItem.image_data}`}
@@ -771,7 +873,7 @@ export default function ClipboardDemo() {
                   )}
                 </div>
                 
-                <button
+                {!selectedHistoryItem.hide_preview && <button
                   onClick={() => {
                     if (selectedHistoryItem.content_type === 'Image' && selectedHistoryItem.image_data) {
                       // Copy image to clipboard
@@ -789,7 +891,7 @@ export default function ClipboardDemo() {
                   className="w-full px-4 py-2 bg-black dark:bg-white text-white dark:text-black rounded-md hover:bg-gray-800 dark:hover:bg-gray-200 transition-colors font-medium"
                 >
                   {selectedHistoryItem.content_type === 'Image' ? 'Copy Image to Clipboard' : 'Copy to Clipboard'}
-                </button>
+                </button>}
               </div>
             </div>
           </div>
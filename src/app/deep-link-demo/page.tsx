@@ -6,12 +6,38 @@ import { listen } from '@tauri-apps/api/event'
 import { Link2, Globe, Settings, User, FileText, ExternalLink, CheckCircle, AlertCircle, ArrowLeft, Play } from 'lucide-react'
 import Link from 'next/link'
 
-interface DeepLinkEvent {
-  url: string
+interface QueryParam {
+  key: string
+  value: string
+}
+
+interface DeepLinkRouteMatch {
+  route: string // Route name
+  pattern: string
+  params: Record<string, unknown>
+  window: string // Label of the window the link is delivered to
+}
+
+// Payload of the deep-link-received event
+interface DeepLinkPayload {
+  id: number // Delivery ID to acknowledge with ack_deep_link
+  url: string // Original URL exactly as received
+  normalized_url: string
   scheme: string
-  host: string
+  host: string // Internationalized domains in punycode
+  host_unicode: string
+  port: number | null
+  username: string
+  password: string | null
   path: string
+  path_segments: string[] // Percent-decoded
   query: string
+  query_params: QueryParam[]
+  fragment: string | null
+  route: DeepLinkRouteMatch | null
+}
+
+interface DeepLinkEvent extends DeepLinkPayload {
   timestamp: Date
 }
 
@@ -50,23 +76,26 @@ export default function DeepLinkDemo() {
 
   const setupDeepLinkListener = useCallback(async () => {
     try {
-      const unlisten = await listen('deep-link-received', (event: any) => {
-        const { url, scheme, host, path, query } = event.payload
-        console.log('Deep link received:', { url, scheme, host, path, query })
+      const unlisten = await listen<DeepLinkPayload>('deep-link-received', (event) => {
+        const link = event.payload
+        console.log('Deep link received:', link)
         
         const newEvent: DeepLinkEvent = {
-          url,
-          scheme,
-          host,
-          path,
-          query,
+          ...link,
           timestamp: new Date()
         }
         
-        setDeepLinkHistory(prev => [newEvent, ...prev.slice(0, 9)]) // Keep last 10 entries
-        showMessage(`Deep link received: ${url}`, 'success')
+        setDeepLinkHistory(prev => [newEvent, ...prev.filter(entry => entry.id !== link.id).slice(0, 9)]) // Keep last 10 entries
+        showMessage(`Deep link received: ${link.url}`, 'success')
+
+        // Unacknowledged links are delivered again when the page reports ready
+        invoke('ack_deep_link', { id: link.id }).catch((error) => {
+          console.error('Failed to acknowledge deep link:', error)
+        })
       })
-      
+
+      // Links are queued until this window reports ready, then the pending ones are delivered
+      await invoke('deep_links_ready')
       setIsListening(true)
       console.log('Deep link listener setup successful')
       
@@ -340,9 +369,9 @@ export default function DeepLinkDemo() {
                 </p>
               ) : (
                 <div className="space-y-3 max-h-80 overflow-y-auto">
-                  {deepLinkHistory.map((event, index) => (
+                  {deepLinkHistory.map((event) => (
                     <div
-                      key={index}
+                      key={event.id}
                       className="p-3 border border-gray-300 dark:border-gray-700 rounded-md bg-white dark:bg-black"
                     >
                       <div className="flex items-center justify-between mb-2">
@@ -350,7 +379,7 @@ export default function DeepLinkDemo() {
                           {formatTimestamp(event.timestamp)}
                         </span>
                         <div className="flex items-center gap-1">
-                          {getActionIcon(event.path)}
+                          {getActionIcon([event.host, ...event.path_segments].join('/'))}
                         </div>
                       </div>
                       
@@ -360,10 +389,29 @@ export default function DeepLinkDemo() {
                       
                       <div className="grid grid-cols-2 gap-2 text-xs text-gray-600 dark:text-gray-400">
                         <div><span className="font-medium">Scheme:</span> {event.scheme}</div>
-                        <div><span className="font-medium">Host:</span> {event.host}</div>
-                        <div className="col-span-2"><span className="font-medium">Path:</span> {event.path}</div>
-                        {event.query && (
-                          <div className="col-span-2"><span className="font-medium">Query:</span> {event.query}</div>
+                        <div>
+                          <span className="font-medium">Host:</span> {event.host_unicode || event.host}
+                          {event.port !== null && `:${event.port}`}
+                        </div>
+                        {event.username && (
+                          <div className="col-span-2"><span className="font-medium">User:</span> {event.username}</div>
+                        )}
+                        <div className="col-span-2">
+                          <span className="font-medium">Path:</span> {event.path_segments.length > 0 ? event.path_segments.join(' / ') : event.path}
+                        </div>
+                        {event.query_params.length > 0 && (
+                          <div className="col-span-2">
+                            <span className="font-medium">Query:</span>{' '}
+                            {event.query_params.map(param => `${param.key}=${param.value}`).join(', ')}
+                          </div>
+                        )}
+                        {event.fragment && (
+                          <div className="col-span-2"><span className="font-medium">Fragment:</span> {event.fragment}</div>
+                        )}
+                        {event.route && (
+                          <div className="col-span-2">
+                            <span className="font-medium">Route:</span> {event.route.route} → {event.route.window}
+                          </div>
                         )}
                       </div>
                     </div>